@@ -1,22 +1,32 @@
 use std::sync::Mutex;
 use std::sync::Arc;
-use crate::models::ActorValues;
-use crate::models::models::Character;
+use crate::Error;
+use crate::models::{ ActorValues, BattleEvent, BattleEvents, StatusKind };
+use crate::damage::attack;
+use crate::random::GameRng;
+use crate::models::attack_options::AttackDescription;
+use crate::models::models::{ Character, Item, ItemEffect };
 use crate::models::Enemy;
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum BattleOutcome {
+    Victory,
+    Defeat,
+}
+
+#[derive(Default)]
 pub struct BattleHandler {
     actors: Vec<Arc<Mutex<ActorValues>>>,
     sorted_actors: Vec<Arc<Mutex<ActorValues>>>,
     current_actor_index: usize,
+    /// Whether the status effects of the current actor already ticked at the start of its turn.
+    turn_started: bool,
+    events: BattleEvents,
 }
 
 impl BattleHandler {
     pub fn new() -> BattleHandler {
-        BattleHandler {
-            actors: Vec::new(),
-            sorted_actors: Vec::new(),
-            current_actor_index: 0,
-        }
+        BattleHandler::default()
     }
 
     pub fn add_enemy(mut self, enemy: &Enemy) -> BattleHandler {
@@ -35,6 +45,8 @@ impl BattleHandler {
             actor.initiative = Some(actor.agil)
         });
         self.sorted_actors = self.get_actor_values_by_order();
+        self.current_actor_index = 0;
+        self.turn_started = false;
     }
 
     pub fn get_actor_values_by_order(&self) -> Vec<Arc<Mutex<ActorValues>>> {
@@ -46,26 +58,247 @@ impl BattleHandler {
     }
 
     pub fn get_current_actor(&self) -> Option<Arc<Mutex<ActorValues>>>{
-        let opt = self.sorted_actors.get(self.current_actor_index);
-        match opt {
-            Some(arc) => Some(arc.clone()),
-            None => None,
-        }
+        self.sorted_actors.get(self.current_actor_index).cloned()
+    }
+
+    pub fn get_player_values(&self) -> Option<ActorValues> {
+        self.actors.iter()
+            .map(|actor| actor.lock().unwrap().clone())
+            .find(|actor| actor.is_player)
+    }
+
+    pub fn get_events(&self) -> &BattleEvents {
+        &self.events
     }
 
+    /// Moves on to the next actor in initiative order, wrapping around at the end of a round.
+    /// Defeated actors are skipped.
     pub fn increase_actor_index(&mut self) {
-        self.current_actor_index += 1;
-        if self.current_actor_index >= self.sorted_actors.len() {
-            self.current_actor_index += 0;
+        self.turn_started = false;
+        let amount = self.sorted_actors.len();
+        for _ in 0..amount {
+            self.current_actor_index = (self.current_actor_index + 1) % amount;
+            if self.sorted_actors[self.current_actor_index].lock().unwrap().hp > 0 {
+                break;
+            }
+        }
+    }
+
+    pub fn get_outcome(&self) -> Option<BattleOutcome> {
+        let is_alive = |is_player: bool| self.actors.iter().any(|actor| {
+            let actor = actor.lock().unwrap();
+            actor.is_player == is_player && actor.hp > 0
+        });
+
+        if !is_alive(true) {
+            Some(BattleOutcome::Defeat)
+        } else if !is_alive(false) {
+            Some(BattleOutcome::Victory)
+        } else {
+            None
+        }
+    }
+
+    /// Resolves the players attack against the first enemy still standing
+    /// and lets the enemies act until it is the players turn again.
+    pub fn player_attack(&mut self, rng: &mut GameRng, description: &AttackDescription) -> Result<(), Error> {
+        let player = self.get_player_on_turn("attack")?;
+
+        if let Some(target) = self.find_target(false) {
+            let event = attack(rng, description, &mut player.lock().unwrap(), &mut target.lock().unwrap());
+            self.events.events.push(event);
+        }
+
+        self.end_turn(&player);
+        self.run_enemy_turns(rng);
+
+        Ok(())
+    }
+
+    /// Applies the effects of a consumable as the players turn and lets the enemies act.
+    pub fn player_use_item(&mut self, rng: &mut GameRng, item: &Item) -> Result<(), Error> {
+        let player = self.get_player_on_turn("use an item")?;
+
+        for effect in &item.effects {
+            let event = self.apply_item_effect(rng, item, effect, &player);
+            self.events.events.extend(event);
         }
+
+        self.end_turn(&player);
+        self.run_enemy_turns(rng);
+
+        Ok(())
     }
 
+    fn get_player_on_turn(&self, action: &str) -> Result<Arc<Mutex<ActorValues>>, Error> {
+        if self.get_outcome().is_some() {
+            return Err(Error::GameDataError(format!("Trying to {}, but the battle is over.", action)));
+        }
+
+        match self.get_current_actor() {
+            Some(actor) if actor.lock().unwrap().is_player => Ok(actor),
+            _ => Err(Error::GameDataError(format!("Trying to {}, but it is not the players turn.", action))),
+        }
+    }
+
+    /// Returns no event if there is nobody to apply the effect to.
+    fn apply_item_effect(&self, rng: &mut GameRng, item: &Item, effect: &ItemEffect, player: &Arc<Mutex<ActorValues>>) -> Option<BattleEvent> {
+        // The target has to be found before the player is locked, finding it locks every actor.
+        let enemy = self.find_target(false);
+
+        let mut player = player.lock().unwrap();
+
+        let (target, effect) = match effect {
+            ItemEffect::Heal(dice) => {
+                let hp = dice.roll_amount(rng);
+                player.heal(hp);
+                (player.name.to_owned(), format!("{} healed {} hp", player.name, hp))
+            }
+            ItemEffect::Buff(ability, amount) => {
+                let value = player.get_ability_mut(ability);
+                *value = value.saturating_add(*amount);
+                (player.name.to_owned(), format!("{} gained {} {:?}", player.name, amount, ability))
+            }
+            ItemEffect::Damage(dice) => {
+                let target = enemy?;
+                let mut target = target.lock().unwrap();
+                let damage = dice.roll_amount(rng);
+                target.apply_damage(damage);
+                (target.name.to_owned(), format!("{} lost {} hp", target.name, damage))
+            }
+            ItemEffect::Cure => {
+                player.cure();
+                (player.name.to_owned(), format!("{} is cured", player.name))
+            }
+            ItemEffect::Status(status) if status.kind.is_negative() => {
+                let target = enemy?;
+                let mut target = target.lock().unwrap();
+                target.add_status(status.clone());
+                (target.name.to_owned(), format!("{} got {}", target.name, status))
+            }
+            ItemEffect::Status(status) => {
+                player.add_status(status.clone());
+                (player.name.to_owned(), format!("{} got {}", player.name, status))
+            }
+        };
+
+        Some(BattleEvent {
+            title: item.name.to_owned(),
+            source: player.name.to_owned(),
+            target,
+            effect,
+        })
+    }
+
+    /// Lets every enemy act with the attack of its template until it is the players turn
+    /// or the battle is decided. Actors who can't act because of a status effect are skipped, the player too.
+    pub fn run_enemy_turns(&mut self, rng: &mut GameRng) {
+        while self.get_outcome().is_none() {
+            let actor = match self.get_current_actor() {
+                Some(actor) => actor,
+                None => break,
+            };
+
+            if !self.start_turn(&actor) {
+                self.end_turn(&actor);
+                continue;
+            }
+
+            if actor.lock().unwrap().is_player {
+                break;
+            }
+
+            let values = actor.lock().unwrap().clone();
+            if let (Some(create_actor), Some(target)) = (values.actor, self.find_target(true)) {
+                let attack = create_actor(values).select_attack();
+                let event = attack(rng, &mut actor.lock().unwrap(), &mut target.lock().unwrap());
+                self.events.events.push(event);
+            }
+
+            self.end_turn(&actor);
+        }
+    }
+
+    /// Ticks the effects working at the start of a turn, once per turn.
+    /// Returns whether the actor is able to act.
+    fn start_turn(&mut self, actor: &Arc<Mutex<ActorValues>>) -> bool {
+        let mut actor = actor.lock().unwrap();
+        if !self.turn_started {
+            self.turn_started = true;
+            if let Some(poison) = actor.get_status(&StatusKind::Poison).cloned() {
+                actor.apply_damage(poison.amount);
+                self.events.events.push(build_status_event(&actor, &poison.kind, format!("{} lost {} hp", actor.name, poison.amount)));
+            }
+            if actor.get_status(&StatusKind::Stun).is_some() && actor.hp > 0 {
+                self.events.events.push(build_status_event(&actor, &StatusKind::Stun, format!("{} can't act", actor.name)));
+            }
+        }
+
+        actor.hp > 0 && actor.get_status(&StatusKind::Stun).is_none()
+    }
+
+    /// Ticks the effects working at the end of a turn, counts down every effect
+    /// and moves on to the next actor. Nothing ticks for an actor who was defeated during the turn.
+    fn end_turn(&mut self, actor: &Arc<Mutex<ActorValues>>) {
+        let mut actor = actor.lock().unwrap();
+        if actor.hp > 0 {
+            if let Some(bleed) = actor.get_status(&StatusKind::Bleed).cloned() {
+                actor.apply_damage(bleed.amount);
+                self.events.events.push(build_status_event(&actor, &bleed.kind, format!("{} lost {} hp", actor.name, bleed.amount)));
+            }
+            for ended in actor.count_down_statuses() {
+                self.events.events.push(build_status_event(&actor, &ended.kind, format!("{} wore off", ended.kind)));
+            }
+        }
+        drop(actor);
+
+        self.increase_actor_index();
+    }
+
+    fn find_target(&self, is_player: bool) -> Option<Arc<Mutex<ActorValues>>> {
+        self.sorted_actors.iter()
+            .find(|actor| {
+                let actor = actor.lock().unwrap();
+                actor.is_player == is_player && actor.hp > 0
+            })
+            .cloned()
+    }
+}
+
+fn build_status_event(actor: &ActorValues, kind: &StatusKind, effect: String) -> BattleEvent {
+    BattleEvent {
+        title: kind.to_string(),
+        source: actor.name.to_owned(),
+        target: actor.name.to_owned(),
+        effect,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::models::models::Character;
     use super::*;
+    use crate::models::StatusEffect;
+    use crate::random::new_rng;
+
+    fn build_enemy(id: u16, name: &str, hp: u16, agil: u16) -> Enemy {
+        let mut enemy = Enemy::new(id, name);
+        enemy.hp = hp;
+        enemy.hp_max = hp;
+        enemy.agil = agil;
+        enemy
+    }
+
+    fn build_attack(dmg: usize) -> AttackDescription {
+        AttackDescription {
+            title: "Punch".to_owned(),
+            attack_type: "physical".to_owned(),
+            dmg_min: dmg,
+            dmg_max: dmg,
+            special_effect: "".to_owned(),
+            status_effects: Vec::new(),
+        }
+    }
 
     #[test]
     fn test_add_enemy() {
@@ -92,7 +325,7 @@ mod tests {
 
         let mut enemy_2 = Enemy::new(2, "Paul");
         enemy_2.agil = 1;
-        
+
         let mut handler: BattleHandler = BattleHandler::new()
             .add_player(&character)
             .add_enemy(&enemy_1)
@@ -110,4 +343,197 @@ mod tests {
 
         assert_eq!(ordered.get(0).unwrap().lock().unwrap().name, character.name, "first is the player");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_actor_index_wraps_and_skips_defeated() {
+        let mut character = Character::default();
+        character.agil = 10;
+
+        let mut handler = BattleHandler::new()
+            .add_player(&character)
+            .add_enemy(&build_enemy(1, "Peter", 10, 5))
+            .add_enemy(&build_enemy(2, "Paul", 0, 1));
+        handler.calc_initiative();
+
+        handler.increase_actor_index();
+        assert_eq!(handler.get_current_actor().unwrap().lock().unwrap().name, "Peter");
+
+        handler.increase_actor_index();
+        assert_eq!(handler.get_current_actor().unwrap().lock().unwrap().name, character.name, "Paul is skipped");
+    }
+
+    #[test]
+    fn test_player_attack_wins_battle() {
+        let mut character = Character::default();
+        character.agil = 10;
+
+        let mut handler = BattleHandler::new()
+            .add_player(&character)
+            .add_enemy(&build_enemy(1, "Peter", 5, 1));
+        handler.calc_initiative();
+
+        let mut rng = new_rng(1);
+        handler.run_enemy_turns(&mut rng);
+
+        assert_eq!(handler.get_outcome(), None);
+
+        handler.player_attack(&mut rng, &build_attack(5)).unwrap();
+
+        assert_eq!(handler.get_outcome(), Some(BattleOutcome::Victory));
+        assert_eq!(handler.get_events().events.len(), 1);
+        assert!(handler.player_attack(&mut rng, &build_attack(5)).is_err(), "battle is over");
+    }
+
+    #[test]
+    fn test_player_attack_out_of_turn() {
+        let character = Character::default();
+
+        let mut handler = BattleHandler::new()
+            .add_player(&character)
+            .add_enemy(&build_enemy(1, "Peter", 5, 10));
+        handler.calc_initiative();
+
+        assert!(handler.player_attack(&mut new_rng(1), &build_attack(1)).is_err());
+    }
+
+    #[test]
+    fn test_defeat_without_player() {
+        let handler = BattleHandler::new()
+            .add_enemy(&build_enemy(1, "Peter", 5, 1));
+
+        assert_eq!(handler.get_outcome(), Some(BattleOutcome::Defeat));
+    }
+
+    #[test]
+    fn test_player_use_item() {
+        let mut character = Character::default();
+        character.agil = 10;
+        character.hp = 50;
+
+        let mut handler = BattleHandler::new()
+            .add_player(&character)
+            .add_enemy(&build_enemy(1, "Peter", 30, 1));
+        handler.calc_initiative();
+
+        let item: Item = serde_json::from_str(r#"{ "id": 1, "name": "Brew", "item_type": "Consumable", "effects": [
+            { "t": "Heal", "c": "10" }, { "t": "Buff", "c": ["Strg", 2] }, { "t": "Damage", "c": "4" }
+        ] }"#).unwrap();
+        handler.player_use_item(&mut new_rng(1), &item).unwrap();
+
+        let player = handler.get_player_values().unwrap();
+        assert!(player.hp > 50, "healed before Peter attacks");
+        assert_eq!(player.strg, character.strg + 2);
+
+        let events = &handler.get_events().events;
+        assert_eq!(events[0].effect, format!("{} healed 10 hp", character.name));
+        assert_eq!(events[2].effect, "Peter lost 4 hp");
+        assert_eq!(events.len(), 4, "Peter attacks after the item was used");
+    }
+
+    #[test]
+    fn test_status_effects_tick() {
+        let mut character = Character::default();
+        character.agil = 10;
+
+        let mut handler = BattleHandler::new()
+            .add_player(&character)
+            .add_enemy(&build_enemy(1, "Peter", 30, 1));
+        handler.calc_initiative();
+
+        let mut attack = build_attack(1);
+        attack.status_effects = vec![
+            StatusEffect { kind: StatusKind::Stun, turns: 1, amount: 0 },
+            StatusEffect { kind: StatusKind::Poison, turns: 2, amount: 3 },
+            StatusEffect { kind: StatusKind::Bleed, turns: 2, amount: 2 },
+        ];
+        let mut rng = new_rng(1);
+        handler.player_attack(&mut rng, &attack).unwrap();
+
+        let effects: Vec<String> = handler.get_events().events.iter().map(|event| event.effect.to_owned()).collect();
+        assert_eq!(effects[1..], [
+            "Peter lost 3 hp", "Peter can't act", "Peter lost 2 hp", "Stun wore off",
+        ], "Peter skips the turn");
+        assert_eq!(handler.get_player_values().unwrap().hp, character.hp);
+
+        handler.player_attack(&mut rng, &build_attack(1)).unwrap();
+        let events = &handler.get_events().events;
+        assert_eq!(events[6].effect, "Peter lost 3 hp");
+        assert_eq!(events[7].title, "Melee Attack", "Peter acts again");
+        assert_eq!(events[8].effect, "Peter lost 2 hp");
+        assert_eq!(events[9].effect, "Poison wore off");
+        assert_eq!(events[10].effect, "Bleed wore off");
+    }
+
+    #[test]
+    fn test_stunned_player_skips_turn() {
+        let mut character = Character::default();
+        character.agil = 10;
+
+        let mut handler = BattleHandler::new()
+            .add_player(&character)
+            .add_enemy(&build_enemy(1, "Peter", 30, 1));
+        handler.calc_initiative();
+        handler.get_current_actor().unwrap().lock().unwrap()
+            .add_status(StatusEffect { kind: StatusKind::Stun, turns: 1, amount: 0 });
+
+        handler.run_enemy_turns(&mut new_rng(1));
+
+        let titles: Vec<String> = handler.get_events().events.iter().map(|event| event.title.to_owned()).collect();
+        assert_eq!(titles, vec!["Stun", "Stun", "Melee Attack"]);
+        assert!(handler.get_current_actor().unwrap().lock().unwrap().is_player, "the player is on turn again");
+        assert!(handler.player_attack(&mut new_rng(1), &build_attack(1)).is_ok());
+    }
+
+    #[test]
+    fn test_status_items() {
+        let mut character = Character::default();
+        character.agil = 10;
+
+        let mut handler = BattleHandler::new()
+            .add_player(&character)
+            .add_enemy(&build_enemy(1, "Peter", 30, 1));
+        handler.calc_initiative();
+
+        let item: Item = serde_json::from_str(r#"{ "id": 1, "name": "Flask", "item_type": "Consumable", "effects": [
+            { "t": "Status", "c": { "kind": "DefenceUp", "turns": 3, "amount": 2 } },
+            { "t": "Status", "c": { "kind": "Weakened", "turns": 3, "amount": 1 } }
+        ] }"#).unwrap();
+        handler.player_use_item(&mut new_rng(1), &item).unwrap();
+
+        let player = handler.get_player_values().unwrap();
+        assert_eq!(player.status_effects, vec![StatusEffect { kind: StatusKind::DefenceUp, turns: 2, amount: 2 }]);
+        let enemy = handler.find_target(false).unwrap();
+        assert_eq!(enemy.lock().unwrap().status_effects, vec![StatusEffect { kind: StatusKind::Weakened, turns: 2, amount: 1 }]);
+        assert_eq!(handler.get_events().events[1].effect, "Peter got Weakened 1 (3 turns)");
+
+        handler.get_current_actor().unwrap().lock().unwrap()
+            .add_status(StatusEffect { kind: StatusKind::Poison, turns: 3, amount: 1 });
+        let cure: Item = serde_json::from_str(r#"{ "id": 2, "name": "Antidote", "item_type": "Consumable", "effects": [{ "t": "Cure" }] }"#).unwrap();
+        handler.player_use_item(&mut new_rng(1), &cure).unwrap();
+
+        let player = handler.get_player_values().unwrap();
+        assert_eq!(player.status_effects, vec![StatusEffect { kind: StatusKind::DefenceUp, turns: 1, amount: 2 }]);
+    }
+
+    #[test]
+    fn test_no_status_ticks_after_defeat() {
+        let character = Character::default();
+
+        let mut handler = BattleHandler::new()
+            .add_player(&character)
+            .add_enemy(&build_enemy(1, "Peter", 2, 10));
+        handler.calc_initiative();
+        {
+            let peter = handler.get_current_actor().unwrap();
+            let mut peter = peter.lock().unwrap();
+            peter.add_status(StatusEffect { kind: StatusKind::Poison, turns: 2, amount: 5 });
+            peter.add_status(StatusEffect { kind: StatusKind::Bleed, turns: 2, amount: 5 });
+        }
+
+        handler.run_enemy_turns(&mut new_rng(1));
+
+        let effects: Vec<String> = handler.get_events().events.iter().map(|event| event.effect.to_owned()).collect();
+        assert_eq!(effects, vec!["Peter lost 5 hp"], "no bleeding after the poison defeated Peter");
+        assert_eq!(handler.get_outcome(), Some(BattleOutcome::Victory));
+    }
+}
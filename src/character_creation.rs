@@ -0,0 +1,197 @@
+//! Rules for creating the character of a new run.
+//!
+//! Every ability starts at `MIN_STAT`, or at the value of the chosen class, and can be raised up to `MAX_STAT`.
+//! The abilities together may not exceed `STAT_BUDGET` points.
+
+use std::rc::Rc;
+use rand::prelude::*;
+
+use crate::Error;
+use crate::models::models::{ Ability, Character, CharacterClass };
+use crate::random::GameRng;
+
+pub const STAT_BUDGET: u16 = 8;
+pub const MIN_STAT: u16 = 1;
+pub const MAX_STAT: u16 = 5;
+pub const MAX_NAME_LENGTH: usize = 16;
+
+const ABILITIES: [Ability; 3] = [Ability::Strg, Ability::Agil, Ability::Def];
+
+/// A character in the making.
+#[derive(Debug, Clone)]
+pub struct CharacterDraft {
+    pub character: Character,
+    class: Option<Rc<CharacterClass>>,
+}
+
+impl CharacterDraft {
+    /// Starts with the name and abilities of the character, clamped to the rules of the class.
+    pub fn new(character: &Character, class: Option<Rc<CharacterClass>>) -> CharacterDraft {
+        let mut draft = CharacterDraft { character: Character::default(), class: None };
+        draft.character.name = character.name.chars().take(MAX_NAME_LENGTH).collect();
+
+        draft.set_class(class);
+        for ability in ABILITIES.iter() {
+            while draft.character.get_ability(ability) < character.get_ability(ability) && draft.increase(ability) {}
+        }
+
+        draft
+    }
+
+    pub fn get_class(&self) -> Option<&Rc<CharacterClass>> {
+        self.class.as_ref()
+    }
+
+    /// Switches the class and resets the abilities to its minimum.
+    pub fn set_class(&mut self, class: Option<Rc<CharacterClass>>) {
+        let hp_max = class.as_ref().map_or(Character::default().hp_max, |class| class.hp_max);
+        self.character.class = class.as_ref().map(|class| class.id);
+        self.character.hp_max = hp_max;
+        self.character.hp = hp_max;
+        self.class = class;
+
+        for ability in ABILITIES.iter() {
+            *self.character.get_ability_mut(ability) = self.minimum(ability);
+        }
+    }
+
+    pub fn minimum(&self, ability: &Ability) -> u16 {
+        self.class.as_ref().map_or(MIN_STAT, |class| class.get_ability(ability))
+    }
+
+    pub fn points_left(&self) -> u16 {
+        let spent: u16 = ABILITIES.iter().map(|ability| self.character.get_ability(ability)).sum();
+        STAT_BUDGET.saturating_sub(spent)
+    }
+
+    /// Returns false if the ability is at its maximum or the budget is used up.
+    pub fn increase(&mut self, ability: &Ability) -> bool {
+        let points_left = self.points_left();
+        let value = self.character.get_ability_mut(ability);
+        if *value >= MAX_STAT || points_left == 0 {
+            return false;
+        }
+        *value += 1;
+        true
+    }
+
+    pub fn decrease(&mut self, ability: &Ability) -> bool {
+        let minimum = self.minimum(ability);
+        let value = self.character.get_ability_mut(ability);
+        if *value <= minimum {
+            return false;
+        }
+        *value -= 1;
+        true
+    }
+
+    /// Spends the whole budget on random abilities.
+    pub fn roll(&mut self, rng: &mut GameRng) {
+        for ability in ABILITIES.iter() {
+            *self.character.get_ability_mut(ability) = self.minimum(ability);
+        }
+
+        while self.points_left() > 0 {
+            let open: Vec<&Ability> = ABILITIES.iter()
+                .filter(|ability| self.character.get_ability(ability) < MAX_STAT)
+                .collect();
+            match open.choose(rng) {
+                Some(ability) => self.increase(ability),
+                None => break,
+            };
+        }
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        if self.character.name.chars().count() < MAX_NAME_LENGTH && !c.is_control() {
+            self.character.name.push(c);
+        }
+    }
+
+    pub fn pop_char(&mut self) {
+        self.character.name.pop();
+    }
+
+    pub fn build(&self) -> Result<Character, Error> {
+        if self.character.name.trim().is_empty() {
+            return Err(Error::CharacterError("the character needs a name".to_string()));
+        }
+
+        let mut character = self.character.clone();
+        character.name = character.name.trim().to_string();
+        Ok(character)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::new_rng;
+
+    #[test]
+    fn test_budget() {
+        let mut draft = CharacterDraft::new(&Character::default(), None);
+        assert_eq!(draft.points_left(), 2);
+
+        assert!(draft.increase(&Ability::Strg));
+        assert!(draft.increase(&Ability::Strg));
+        assert!(!draft.increase(&Ability::Agil), "budget is used up");
+
+        assert!(draft.decrease(&Ability::Def));
+        assert!(!draft.decrease(&Ability::Def), "minimum reached");
+        assert!(draft.increase(&Ability::Strg));
+        assert_eq!(draft.character.strg, MAX_STAT);
+        assert!(!draft.increase(&Ability::Strg), "maximum reached");
+    }
+
+    #[test]
+    fn test_clamps_to_rules() {
+        let mut character = Character::default();
+        character.strg = 9;
+        character.name = "A name much too long to fit".to_string();
+
+        let draft = CharacterDraft::new(&character, None);
+        assert_eq!(draft.character.strg, MAX_STAT);
+        assert_eq!(draft.points_left(), 0);
+        assert_eq!(draft.character.name.len(), MAX_NAME_LENGTH);
+    }
+
+    #[test]
+    fn test_roll_spends_budget() {
+        let mut rng = new_rng(1);
+        let mut draft = CharacterDraft::new(&Character::default(), None);
+        for _ in 0..20 {
+            draft.roll(&mut rng);
+            assert_eq!(draft.points_left(), 0);
+            assert!(ABILITIES.iter().all(|ability| (MIN_STAT..=MAX_STAT).contains(&draft.character.get_ability(ability))));
+        }
+    }
+
+    #[test]
+    fn test_build() {
+        let mut draft = CharacterDraft::new(&Character::default(), None);
+        draft.character.name.clear();
+        assert!(draft.build().is_err());
+
+        " Kim ".chars().for_each(|c| draft.push_char(c));
+        assert_eq!(draft.build().unwrap().name, "Kim");
+    }
+
+    #[test]
+    fn test_class() {
+        let class: CharacterClass = serde_json::from_str(
+            r#"{ "id": 2, "name": "Rogue", "hp_max": 90, "strg": 1, "agil": 3, "def": 1 }"#).unwrap();
+        let mut draft = CharacterDraft::new(&Character::default(), Some(Rc::new(class)));
+
+        assert_eq!(draft.character.class, Some(2));
+        assert_eq!(draft.character.hp_max, 90);
+        assert_eq!(draft.character.agil, 3, "raised to the minimum of the class");
+        assert_eq!(draft.points_left(), 1);
+        assert!(!draft.decrease(&Ability::Agil));
+
+        draft.set_class(None);
+        assert_eq!(draft.character.class, None);
+        assert_eq!(draft.character.agil, MIN_STAT);
+        assert_eq!(draft.character.hp_max, 100);
+    }
+}
@@ -0,0 +1,152 @@
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage: rusty-dungeon [--data-dir <dir>]... [--seed <number>] [--record <file>] [--replay <file>] [--check-data]\n       rusty-dungeon --simulate <runs> [--policy random|greedy|first] [--level <id>] [--seed <number>] [--data-dir <dir>]...";
+
+const DEFAULT_DATA_DIR: &str = "data";
+
+/// Options given on the command line.
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    /// The base data directory followed by content packs layered on top of it.
+    pub data_dirs: Vec<PathBuf>,
+    pub check_data: bool,
+    pub seed: Option<u64>,
+    /// Writes every command of the session to this file on exit.
+    pub record: Option<String>,
+    /// Replays the commands of this file before handing over to the player.
+    pub replay: Option<String>,
+    /// Plays this many runs without a terminal and prints their statistics.
+    pub simulate: Option<u32>,
+    pub policy: String,
+    pub level: Option<u16>,
+}
+
+impl Options {
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+        let mut data_dirs = Vec::new();
+        let mut check_data = false;
+        let mut seed = None;
+        let mut record = None;
+        let mut replay = None;
+        let mut simulate = None;
+        let mut policy = "random".to_string();
+        let mut level = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--check-data" => check_data = true,
+                "--data-dir" => match args.next() {
+                    Some(dir) => data_dirs.push(PathBuf::from(dir)),
+                    None => return Err("--data-dir needs a directory".to_string()),
+                },
+                "--seed" => match args.next().map(|value| value.parse::<u64>()) {
+                    Some(Ok(value)) => seed = Some(value),
+                    _ => return Err("--seed needs a positive number".to_string()),
+                },
+                "--record" => match args.next() {
+                    Some(file) => record = Some(file),
+                    None => return Err("--record needs a file".to_string()),
+                },
+                "--replay" => match args.next() {
+                    Some(file) => replay = Some(file),
+                    None => return Err("--replay needs a file".to_string()),
+                },
+                "--simulate" => match args.next().map(|value| value.parse::<u32>()) {
+                    Some(Ok(value)) => simulate = Some(value),
+                    _ => return Err("--simulate needs the amount of runs".to_string()),
+                },
+                "--policy" => match args.next() {
+                    Some(name) => policy = name,
+                    None => return Err("--policy needs a name".to_string()),
+                },
+                "--level" => match args.next().map(|value| value.parse::<u16>()) {
+                    Some(Ok(value)) => level = Some(value),
+                    _ => return Err("--level needs a level id".to_string()),
+                },
+                _ => return Err(format!("unknown argument {}", arg)),
+            }
+        }
+
+        if data_dirs.is_empty() {
+            data_dirs.push(default_data_dir());
+        }
+
+        if seed.is_some() && replay.is_some() {
+            return Err("--seed can not be combined with --replay".to_string());
+        }
+
+        Ok(Options { data_dirs, check_data, seed, record, replay, simulate, policy, level })
+    }
+}
+
+/// Uses `./data` when it exists and falls back to the `data` directory next to the executable.
+fn default_data_dir() -> PathBuf {
+    let local = PathBuf::from(DEFAULT_DATA_DIR);
+    if local.is_dir() {
+        return local;
+    }
+
+    std::env::current_exe().ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(DEFAULT_DATA_DIR)))
+        .filter(|dir| dir.is_dir())
+        .unwrap_or(local)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_defaults() {
+        let options = parse(&[]).unwrap();
+        assert_eq!(options.data_dirs.len(), 1);
+        assert!(!options.check_data);
+        assert_eq!(options.seed, None);
+        assert_eq!(options.record, None);
+        assert_eq!(options.replay, None);
+        assert_eq!(options.simulate, None);
+        assert_eq!(options.policy, "random");
+    }
+
+    #[test]
+    fn test_seed() {
+        assert_eq!(parse(&["--seed", "42"]).unwrap().seed, Some(42));
+        assert!(parse(&["--seed", "-1"]).is_err());
+        assert!(parse(&["--seed"]).is_err());
+    }
+
+    #[test]
+    fn test_data_dirs() {
+        let options = parse(&["--data-dir", "base", "--check-data", "--data-dir", "mod"]).unwrap();
+        assert_eq!(options.data_dirs, vec![PathBuf::from("base"), PathBuf::from("mod")]);
+        assert!(options.check_data);
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let options = parse(&["--replay", "old.json", "--record", "new.json"]).unwrap();
+        assert_eq!(options.replay, Some("old.json".to_string()));
+        assert_eq!(options.record, Some("new.json".to_string()));
+        assert!(parse(&["--replay", "old.json", "--seed", "1"]).is_err());
+    }
+
+    #[test]
+    fn test_simulate() {
+        let options = parse(&["--simulate", "1000", "--policy", "greedy", "--level", "2"]).unwrap();
+        assert_eq!(options.simulate, Some(1000));
+        assert_eq!(options.policy, "greedy");
+        assert_eq!(options.level, Some(2));
+        assert!(parse(&["--simulate", "many"]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        assert!(parse(&["--data-dir"]).is_err());
+        assert!(parse(&["--record"]).is_err());
+        assert!(parse(&["--fly"]).is_err());
+    }
+}
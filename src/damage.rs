@@ -0,0 +1,156 @@
+use std::convert::TryFrom;
+use rand::prelude::*;
+use crate::models::{ ActorValues, BattleEvent };
+use crate::models::attack_options::AttackDescription;
+use crate::models::models::Ability;
+use crate::random::{ roll_nd6_against, GameRng, DEFAULT_N6_TRESHOLD };
+
+/// Damage of a single attack.
+///
+/// The base damage is rolled from the range of the attack. The attacker rolls a d6 per point of
+/// `strg` and every success adds one point of damage. The defender rolls a d6 per point of `def`
+/// and every success blocks one point of damage. Status effects of both are taken into account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Damage {
+    pub base: u16,
+    pub bonus: u16,
+    pub blocked: u16,
+}
+
+impl Damage {
+    pub fn roll(rng: &mut GameRng, description: &AttackDescription, attacker: &ActorValues, defender: &ActorValues) -> Damage {
+        let dmg_max = description.dmg_max.max(description.dmg_min);
+        let base = u16::try_from(rng.gen_range(description.dmg_min..=dmg_max)).unwrap_or(u16::MAX);
+        let (bonus, _) = roll_nd6_against(rng, attacker.get_effective_ability(&Ability::Strg), DEFAULT_N6_TRESHOLD);
+        let (blocked, _) = roll_nd6_against(rng, defender.get_effective_ability(&Ability::Def), DEFAULT_N6_TRESHOLD);
+
+        Damage { base, bonus, blocked }
+    }
+
+    pub fn total(&self) -> u16 {
+        self.base.saturating_add(self.bonus).saturating_sub(self.blocked)
+    }
+}
+
+/// Rolls the damage of the attack, applies it to the target and describes the result.
+/// The status effects of the attack only stick to a target which took damage and is still standing.
+pub fn attack(rng: &mut GameRng, description: &AttackDescription, user: &mut ActorValues, target: &mut ActorValues) -> BattleEvent {
+    let damage = Damage::roll(rng, description, user, target);
+    target.apply_damage(damage.total());
+
+    let mut effect = build_effect_text(&target.name, &damage);
+    if damage.total() > 0 && target.hp > 0 {
+        for status in &description.status_effects {
+            target.add_status(status.clone());
+            effect.push_str(&format!(", {}", status));
+        }
+    }
+
+    BattleEvent {
+        title: description.title.to_owned(),
+        source: user.name.to_owned(),
+        target: target.name.to_owned(),
+        effect,
+    }
+}
+
+fn build_effect_text(target: &str, damage: &Damage) -> String {
+    format!("{} lost {} hp ({} +{} strg -{} def)", target, damage.total(), damage.base, damage.bonus, damage.blocked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ Enemy, StatusEffect, StatusKind };
+    use crate::random::new_rng;
+
+    fn build_actor(hp: u16, strg: u16, def: u16) -> ActorValues {
+        let mut enemy = Enemy::new(1, "Peter");
+        enemy.hp = hp;
+        enemy.hp_max = hp;
+        enemy.strg = strg;
+        enemy.def = def;
+        (&enemy).into()
+    }
+
+    fn build_attack(dmg_min: usize, dmg_max: usize) -> AttackDescription {
+        AttackDescription {
+            title: "Punch".to_owned(),
+            attack_type: "physical".to_owned(),
+            dmg_min,
+            dmg_max,
+            special_effect: "".to_owned(),
+            status_effects: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_total() {
+        let damage = Damage { base: 3, bonus: 2, blocked: 1 };
+        assert_eq!(damage.total(), 4);
+    }
+
+    #[test]
+    fn test_total_fully_blocked() {
+        let damage = Damage { base: 1, bonus: 0, blocked: 4 };
+        assert_eq!(damage.total(), 0);
+    }
+
+    #[test]
+    fn test_huge_damage_saturates() {
+        let damage = Damage { base: u16::MAX, bonus: 2, blocked: 1 };
+        assert_eq!(damage.total(), u16::MAX - 1);
+
+        let attacker = build_actor(10, 0, 0);
+        let defender = build_actor(10, 0, 0);
+        let damage = Damage::roll(&mut new_rng(1), &build_attack(70000, 70000), &attacker, &defender);
+        assert_eq!(damage.base, u16::MAX);
+    }
+
+    #[test]
+    fn test_roll_without_abilities() {
+        let attacker = build_actor(10, 0, 0);
+        let defender = build_actor(10, 0, 0);
+        let damage = Damage::roll(&mut new_rng(1), &build_attack(2, 2), &attacker, &defender);
+        assert_eq!(damage, Damage { base: 2, bonus: 0, blocked: 0 });
+    }
+
+    #[test]
+    fn test_roll_bounds() {
+        let attacker = build_actor(10, 3, 0);
+        let defender = build_actor(10, 0, 2);
+        let mut rng = new_rng(1);
+        for _ in 0..20 {
+            let damage = Damage::roll(&mut rng, &build_attack(1, 4), &attacker, &defender);
+            assert!((1..=4).contains(&damage.base));
+            assert!(damage.bonus <= 3);
+            assert!(damage.blocked <= 2);
+        }
+    }
+
+    #[test]
+    fn test_attack_applies_damage() {
+        let mut attacker = build_actor(10, 0, 0);
+        let mut defender = build_actor(10, 0, 0);
+        let event = attack(&mut new_rng(1), &build_attack(4, 4), &mut attacker, &mut defender);
+        assert_eq!(defender.hp, 6);
+        assert_eq!(event.effect, "Peter lost 4 hp (4 +0 strg -0 def)");
+    }
+
+    #[test]
+    fn test_attack_applies_status_effects() {
+        let mut attacker = build_actor(10, 0, 0);
+        let mut defender = build_actor(10, 0, 0);
+        let mut description = build_attack(4, 4);
+        description.status_effects = vec![StatusEffect { kind: StatusKind::Poison, turns: 3, amount: 2 }];
+
+        let event = attack(&mut new_rng(1), &description, &mut attacker, &mut defender);
+        assert_eq!(defender.status_effects, description.status_effects);
+        assert_eq!(event.effect, "Peter lost 4 hp (4 +0 strg -0 def), Poison 2 (3 turns)");
+
+        let mut blocked = build_attack(0, 0);
+        blocked.status_effects = description.status_effects.clone();
+        attack(&mut new_rng(1), &blocked, &mut defender, &mut attacker);
+        assert!(attacker.status_effects.is_empty(), "no damage, no poison");
+    }
+}
@@ -0,0 +1,129 @@
+use std::fs;
+use std::path::Path;
+use serde::de::DeserializeOwned;
+
+use crate::Error;
+use crate::data::{ WithId, DataProblem, validate, LEVELS_FILE, ROOMS_FILE, ITEMS_FILE, SKILLS_FILE, ENCOUNTERS_FILE, ENEMIES_FILE, DAMAGE_FILE, CLASSES_FILE };
+use crate::models::{ Encounter, Enemy };
+use crate::models::models::{ Level, Room, Item, Skill, DamageRange, CharacterClass };
+
+/// The raw content of one data directory. Several packs can be layered on top of each other,
+/// entries of later packs add to or replace entries with the same id.
+#[derive(Default)]
+pub struct DataPack {
+    pub levels: Vec<Level>,
+    pub rooms: Vec<Room>,
+    pub items: Vec<Item>,
+    pub skills: Vec<Skill>,
+    pub encounters: Vec<Encounter>,
+    pub enemies: Vec<Enemy>,
+    pub damage: Vec<DamageRange>,
+    pub classes: Vec<CharacterClass>,
+}
+
+impl DataPack {
+    /// Reads all collections from the directory. Missing files are only reported when `complete` is set,
+    /// content packs may ship just the files they change. Every failure is recorded in `problems`.
+    pub fn read_from_dir(dir: &Path, complete: bool, problems: &mut Vec<String>) -> DataPack {
+        DataPack {
+            levels: read_db(dir, LEVELS_FILE, complete, problems),
+            rooms: read_db(dir, ROOMS_FILE, complete, problems),
+            items: read_db(dir, ITEMS_FILE, complete, problems),
+            skills: read_db(dir, SKILLS_FILE, complete, problems),
+            encounters: read_db(dir, ENCOUNTERS_FILE, complete, problems),
+            enemies: read_db(dir, ENEMIES_FILE, complete, problems),
+            damage: read_db(dir, DAMAGE_FILE, complete, problems),
+            classes: read_db(dir, CLASSES_FILE, complete, problems),
+        }
+    }
+
+    /// Reads the base directory and layers the content packs of the following directories on top of it.
+    pub fn read_layered<P: AsRef<Path>>(dirs: &[P], problems: &mut Vec<String>) -> DataPack {
+        let mut pack = DataPack::default();
+        for (index, dir) in dirs.iter().enumerate() {
+            pack.merge(DataPack::read_from_dir(dir.as_ref(), index == 0, problems));
+        }
+        pack
+    }
+
+    pub fn validate(&self) -> Vec<DataProblem> {
+        validate(self)
+    }
+
+    pub fn merge(&mut self, other: DataPack) {
+        merge_by_id(&mut self.levels, other.levels);
+        merge_by_id(&mut self.rooms, other.rooms);
+        merge_by_id(&mut self.items, other.items);
+        merge_by_id(&mut self.skills, other.skills);
+        merge_by_id(&mut self.encounters, other.encounters);
+        merge_by_id(&mut self.enemies, other.enemies);
+        merge_by_id(&mut self.damage, other.damage);
+        merge_by_id(&mut self.classes, other.classes);
+    }
+}
+
+fn read_db<T: DeserializeOwned>(dir: &Path, file: &str, complete: bool, problems: &mut Vec<String>) -> Vec<T> {
+    let path = dir.join(file);
+    if !complete && !path.exists() {
+        return Vec::new();
+    }
+
+    let parsed = fs::read_to_string(&path)
+        .map_err(Error::from)
+        .and_then(|content| serde_json::from_str(&content).map_err(Error::from));
+
+    match parsed {
+        Ok(list) => list,
+        Err(err) => {
+            problems.push(format!("{}: {}", path.display(), err));
+            Vec::new()
+        }
+    }
+}
+
+fn merge_by_id<T: WithId>(base: &mut Vec<T>, entries: Vec<T>) {
+    for entry in entries {
+        match base.iter().position(|existing| existing.get_id() == entry.get_id()) {
+            Some(index) => base[index] = entry,
+            None => base.push(entry),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_items(json: &str) -> Vec<Item> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_merge_overrides_and_adds() {
+        let mut base = DataPack {
+            items: build_items(r#"[{ "id": 1, "name": "Sword", "item_type": "Weapon" }, { "id": 2, "name": "Armor", "item_type": "Armor" }]"#),
+            ..DataPack::default()
+        };
+        let mod_pack = DataPack {
+            items: build_items(r#"[{ "id": 2, "name": "Mithril Armor", "item_type": "Armor" }, { "id": 3, "name": "Axe", "item_type": "Weapon" }]"#),
+            ..DataPack::default()
+        };
+
+        base.merge(mod_pack);
+
+        let names: Vec<&str> = base.items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, vec!["Sword", "Mithril Armor", "Axe"]);
+    }
+
+    #[test]
+    fn test_read_missing_files() {
+        let dir = Path::new("./does-not-exist");
+
+        let mut problems = Vec::new();
+        DataPack::read_from_dir(dir, false, &mut problems);
+        assert!(problems.is_empty(), "content packs may skip files");
+
+        DataPack::read_from_dir(dir, true, &mut problems);
+        assert_eq!(problems.len(), 8);
+    }
+}
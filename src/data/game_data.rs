@@ -1,9 +1,12 @@
 use crate::Enemy;
 use crate::Encounter;
 use crate::Error;
-use crate::models::models::{ Level, Room, Item, Skill };
+use crate::data::DataPack;
+use crate::entities::find_template;
+use crate::models::models::{ Level, Room, Item, Skill, DamageRange, CharacterClass };
 use std::rc::Rc;
 
+#[derive(Clone)]
 pub struct GameData {
     levels: Vec<Rc<Level>>,
     rooms: Vec<Rc<Room>>,
@@ -11,6 +14,8 @@ pub struct GameData {
     skills: Vec<Rc<Skill>>,
     encounters: Vec<Rc<Encounter>>,
     enemies: Vec<Rc<Enemy>>,
+    damage: Vec<DamageRange>,
+    classes: Vec<Rc<CharacterClass>>,
 }
 
 impl GameData {
@@ -20,16 +25,31 @@ impl GameData {
         items:Vec<Item>,
         skills: Vec<Skill>,
         encounters: Vec<Encounter>,
-        enemies: Vec<Enemy>
-    ) -> GameData {
-        GameData {
+        enemies: Vec<Enemy>,
+        damage: Vec<DamageRange>,
+    ) -> Result<GameData, Error> {
+        let mut enemies = enemies;
+        for enemy in enemies.iter_mut() {
+            enemy.actor = Some(find_template(&enemy.template)?);
+        }
+
+        Ok(GameData {
             levels: levels.iter().map(|level| Rc::new(level.clone())).collect(),
             rooms: rooms.iter().map(|room| Rc::new(room.clone())).collect(),
             items: items.iter().map(|item| Rc::new(item.clone())).collect(),
             skills: skills.iter().map(|item| Rc::new(item.clone())).collect(),
             encounters: encounters.iter().map(|item| Rc::new(item.clone())).collect(),
             enemies: enemies.iter().map(|item| Rc::new(item.clone())).collect(),
-        }
+            damage,
+            classes: Vec::new(),
+        })
+    }
+
+    pub fn from_pack(pack: DataPack) -> Result<GameData, Error> {
+        let DataPack { levels, rooms, items, skills, encounters, enemies, damage, classes } = pack;
+        let mut game_data = GameData::new(levels, rooms, items, skills, encounters, enemies, damage)?;
+        game_data.classes = classes.into_iter().map(Rc::new).collect();
+        Ok(game_data)
     }
 
     pub fn find_item_by_id(&self, item_id: u16) -> Result<&Rc<Item>, Error> {
@@ -39,6 +59,13 @@ impl GameData {
         }
     }
 
+    pub fn find_first_level(&self) -> Result<&Rc<Level>, Error> {
+        match self.levels.first() {
+            Some(level) => Ok(level),
+            None => Err(Error::GameDataError("No levels found".to_string())),
+        }
+    }
+
     pub fn find_level_by_id(&self, level_id: u16) -> Result<&Rc<Level>, Error> {
         match self.levels.iter().find(|level| level.id == level_id) {
             Some(level) => Ok(level),
@@ -66,6 +93,21 @@ impl GameData {
         self.find_by_id(&self.encounters, encounter_id)
     }
 
+    pub fn find_class_by_id(&self, class_id: u16) -> Result<Rc<CharacterClass>, Error> {
+        self.find_by_id(&self.classes, class_id)
+    }
+
+    pub fn get_classes(&self) -> &[Rc<CharacterClass>] {
+        &self.classes
+    }
+
+    pub fn find_damage_range(&self, dificulty: u8) -> Result<&DamageRange, Error> {
+        match DamageRange::find(&self.damage, dificulty) {
+            Some(range) => Ok(range),
+            None => Err(Error::GameDataError(format!("Could not find damage range for difficulty: {}", dificulty))),
+        }
+    }
+
     pub fn find_by_id<T: WithId>(&self, list: &Vec<Rc<T>>, id: u16) -> Result<Rc<T>, Error> {
         match list.iter().find(|item| item.get_id() == id) {
             Some(skill) => Ok(Rc::clone(skill)),
@@ -1,3 +1,7 @@
 mod game_data;
+mod validation;
+mod data_pack;
 
-pub use game_data::*;
\ No newline at end of file
+pub use game_data::*;
+pub use validation::*;
+pub use data_pack::*;
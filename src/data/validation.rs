@@ -0,0 +1,430 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::character_creation::{ STAT_BUDGET, MIN_STAT, MAX_STAT };
+use crate::data::{ DataPack, WithId };
+use crate::entities::find_template;
+use crate::models::{ EnemyAmount, EnemyDeclaration, StatusEffect };
+use crate::models::attack_options::AttackDescription;
+use crate::models::models::{ Ability, RoomResult, DamageRange, ItemEffect, ItemType };
+
+pub const LEVELS_FILE: &str = "levels.json";
+pub const ROOMS_FILE: &str = "rooms.json";
+pub const ITEMS_FILE: &str = "items.json";
+pub const SKILLS_FILE: &str = "skills.json";
+pub const ENCOUNTERS_FILE: &str = "encounters.json";
+pub const ENEMIES_FILE: &str = "enemies.json";
+pub const DAMAGE_FILE: &str = "damage.json";
+pub const CLASSES_FILE: &str = "classes.json";
+
+/// A single problem found in the game data, pointing to the file and the id of the broken entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataProblem {
+    pub file: &'static str,
+    pub id: u16,
+    pub message: String,
+}
+
+impl fmt::Display for DataProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (id {}): {}", self.file, self.id, self.message)
+    }
+}
+
+/// Cross checks all collections and returns every problem found instead of stopping at the first one.
+pub fn validate(pack: &DataPack) -> Vec<DataProblem> {
+    let DataPack { levels, rooms, items, skills, encounters, enemies, damage, classes } = pack;
+    let mut problems = Vec::new();
+
+    find_duplicates(LEVELS_FILE, levels, &mut problems);
+    find_duplicates(ROOMS_FILE, rooms, &mut problems);
+    find_duplicates(ITEMS_FILE, items, &mut problems);
+    find_duplicates(SKILLS_FILE, skills, &mut problems);
+    find_duplicates(ENCOUNTERS_FILE, encounters, &mut problems);
+    find_duplicates(ENEMIES_FILE, enemies, &mut problems);
+    find_duplicates(DAMAGE_FILE, damage, &mut problems);
+    find_duplicates(CLASSES_FILE, classes, &mut problems);
+
+    let room_ids = collect_ids(rooms);
+    let level_ids = collect_ids(levels);
+    let item_ids = collect_ids(items);
+    let skill_ids = collect_ids(skills);
+    let encounter_ids = collect_ids(encounters);
+    let enemy_ids = collect_ids(enemies);
+
+    for level in levels {
+        let mut check = |kind: &str, ids: &HashSet<u16>, id: u16| {
+            if !ids.contains(&id) {
+                problems.push(dangling(LEVELS_FILE, level.id, kind, id));
+            }
+        };
+        level.rooms.iter().for_each(|room_id| check("room", &room_ids, *room_id));
+        check("first room", &room_ids, level.first_room);
+        check("final room", &room_ids, level.final_room);
+        if let Some(next_level) = level.next_level {
+            check("next level", &level_ids, next_level);
+        }
+    }
+
+    for room in rooms {
+        let mut check = |kind: &str, ids: &HashSet<u16>, id: u16| {
+            if !ids.contains(&id) {
+                problems.push(dangling(ROOMS_FILE, room.id, kind, id));
+            }
+        };
+        for choice in &room.choices {
+            if let Some(next) = choice.next {
+                check("next room", &room_ids, next);
+            }
+            for consequence in choice.all_consequences() {
+                match consequence {
+                    RoomResult::GainItem(id) => check("item", &item_ids, *id),
+                    RoomResult::GainSkill(id) => check("skill", &skill_ids, *id),
+                    RoomResult::StartFight(id) => check("encounter", &encounter_ids, *id),
+                    RoomResult::NextRoom(id) => check("next room", &room_ids, *id),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    for room in rooms {
+        let checks = room.choices.iter()
+            .flat_map(|choice| choice.all_consequences())
+            .filter_map(|consequence| match consequence {
+                RoomResult::AbilityCheck(_, dificulty) => Some(*dificulty),
+                _ => None,
+            });
+        for dificulty in checks {
+            if DamageRange::find(damage, dificulty).is_none() {
+                problems.push(DataProblem {
+                    file: ROOMS_FILE,
+                    id: room.id,
+                    message: format!("no damage range for difficulty {}", dificulty),
+                });
+            }
+        }
+    }
+
+    for encounter in encounters {
+        for EnemyDeclaration(enemy_id, amount) in &encounter.enemies {
+            if !enemy_ids.contains(enemy_id) {
+                problems.push(dangling(ENCOUNTERS_FILE, encounter.id, "enemy", *enemy_id));
+            }
+            if let EnemyAmount::Range(min, max) = amount {
+                if min > max {
+                    problems.push(DataProblem {
+                        file: ENCOUNTERS_FILE,
+                        id: encounter.id,
+                        message: format!("invalid range {}-{} for enemy {}", min, max, enemy_id),
+                    });
+                }
+            }
+        }
+    }
+
+    for range in damage {
+        if range.damage.min() < 0 {
+            problems.push(DataProblem {
+                file: DAMAGE_FILE,
+                id: range.get_id(),
+                message: format!("damage {} can be negative", range.damage),
+            });
+        }
+    }
+
+    for class in classes {
+        let mut problem = |message: String| problems.push(DataProblem { file: CLASSES_FILE, id: class.id, message });
+
+        class.items.iter()
+            .filter(|id| !item_ids.contains(id))
+            .for_each(|id| problem(format!("references missing item {}", id)));
+        class.skills.iter()
+            .filter(|id| !skill_ids.contains(id))
+            .for_each(|id| problem(format!("references missing skill {}", id)));
+
+        let abilities = [Ability::Strg, Ability::Agil, Ability::Def];
+        if abilities.iter().any(|ability| !(MIN_STAT..=MAX_STAT).contains(&class.get_ability(ability))) {
+            problem(format!("abilities have to be between {} and {}", MIN_STAT, MAX_STAT));
+        }
+        let total: u16 = abilities.iter().map(|ability| class.get_ability(ability)).sum();
+        if total > STAT_BUDGET {
+            problem(format!("abilities add up to {}, more than the budget of {}", total, STAT_BUDGET));
+        }
+
+        if class.attacks.len() > 3 {
+            problem(format!("{} attacks, a class can have at most 3", class.attacks.len()));
+        }
+        check_attacks(CLASSES_FILE, class.id, &class.attacks, &mut problems);
+    }
+
+    for item in items {
+        if item.item_type != ItemType::Weapon && !item.attacks.is_empty() {
+            problems.push(DataProblem {
+                file: ITEMS_FILE,
+                id: item.id,
+                message: "only weapons can have attacks".to_string(),
+            });
+        }
+        check_attacks(ITEMS_FILE, item.id, &item.attacks, &mut problems);
+
+        let mut problem = |message: &str| problems.push(DataProblem { file: ITEMS_FILE, id: item.id, message: message.to_string() });
+        match (&item.item_type, item.effects.is_empty()) {
+            (ItemType::Consumable, true) => problem("consumables need at least one effect"),
+            (ItemType::Consumable, false) => {},
+            (_, false) => problem("only consumables can have effects"),
+            (_, true) => {},
+        }
+        for effect in &item.effects {
+            match effect {
+                ItemEffect::Heal(dice) | ItemEffect::Damage(dice) if dice.min() < 0 => {
+                    problem(&format!("effect {} can be negative", effect));
+                }
+                ItemEffect::Status(status) => {
+                    check_status(status).into_iter().for_each(|message| problem(&message));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for skill in skills {
+        check_attacks(SKILLS_FILE, skill.id, &skill.attacks, &mut problems);
+    }
+
+    for enemy in enemies {
+        if find_template(&enemy.template).is_err() {
+            problems.push(DataProblem {
+                file: ENEMIES_FILE,
+                id: enemy.id,
+                message: format!("unknown template {}", enemy.template),
+            });
+        }
+    }
+
+    problems
+}
+
+fn collect_ids<T: WithId>(list: &[T]) -> HashSet<u16> {
+    list.iter().map(|entry| entry.get_id()).collect()
+}
+
+fn find_duplicates<T: WithId>(file: &'static str, list: &[T], problems: &mut Vec<DataProblem>) {
+    let mut seen = HashSet::new();
+    for entry in list {
+        let id = entry.get_id();
+        if !seen.insert(id) {
+            problems.push(DataProblem { file, id, message: "duplicate id".to_string() });
+        }
+    }
+}
+
+fn check_attacks(file: &'static str, id: u16, attacks: &[AttackDescription], problems: &mut Vec<DataProblem>) {
+    for attack in attacks.iter().filter(|attack| attack.dmg_min > attack.dmg_max) {
+        problems.push(DataProblem {
+            file,
+            id,
+            message: format!("invalid damage {}-{} of attack {}", attack.dmg_min, attack.dmg_max, attack.title),
+        });
+    }
+    for attack in attacks {
+        for message in attack.status_effects.iter().filter_map(check_status) {
+            problems.push(DataProblem { file, id, message: format!("attack {}: {}", attack.title, message) });
+        }
+    }
+}
+
+/// A status effect without turns or without the amount it needs does nothing.
+fn check_status(status: &StatusEffect) -> Option<String> {
+    if status.turns == 0 {
+        Some(format!("status effect {} lasts no turn", status.kind))
+    } else if status.kind.needs_amount() && status.amount == 0 {
+        Some(format!("status effect {} needs an amount", status.kind))
+    } else {
+        None
+    }
+}
+
+fn dangling(file: &'static str, id: u16, kind: &str, reference: u16) -> DataProblem {
+    DataProblem {
+        file,
+        id,
+        message: format!("references missing {} {}", kind, reference),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ Encounter, Enemy };
+    use crate::models::models::{ Level, Room, Item, CharacterClass };
+
+    fn parse<T: serde::de::DeserializeOwned>(json: &str) -> Vec<T> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn build_levels() -> Vec<Level> {
+        parse(r#"[{ "name": "Test", "id": 1, "rooms": [2], "level_points": 10, "first_room": 1, "final_room": 2 }]"#)
+    }
+
+    fn build_rooms() -> Vec<Room> {
+        parse(r#"[
+            { "id": 1, "title": "A", "text": "", "choices": [{ "text": "go", "consequences": [{ "t": "GainItem", "c": 1 }] }] },
+            { "id": 2, "title": "B", "text": "", "choices": [{ "text": "go", "consequences": [{ "t": "StartFight", "c": 1 }] }] }
+        ]"#)
+    }
+
+    fn build_encounters() -> Vec<Encounter> {
+        parse(r#"[{ "id": 1, "text": "", "enemies": [[1, {"Range": [1, 3]}]] }]"#)
+    }
+
+    fn build_enemies() -> Vec<Enemy> {
+        parse(r#"[{ "id": 1, "name": "Cultist", "template": "basic_melee_fighter", "hp": 30, "strg": 2, "agil": 1, "def": 1 }]"#)
+    }
+
+    fn build_items() -> Vec<Item> {
+        parse(r#"[{ "id": 1, "name": "Sword", "item_type": "Weapon" }]"#)
+    }
+
+    fn build_pack() -> DataPack {
+        DataPack {
+            levels: build_levels(),
+            rooms: build_rooms(),
+            items: build_items(),
+            encounters: build_encounters(),
+            enemies: build_enemies(),
+            ..DataPack::default()
+        }
+    }
+
+    #[test]
+    fn test_valid_data() {
+        let problems = validate(&build_pack());
+        assert_eq!(problems, Vec::new());
+    }
+
+    #[test]
+    fn test_duplicate_ids() {
+        let mut enemies = build_enemies();
+        enemies.push(enemies[0].clone());
+
+        let problems = validate(&DataPack { enemies, ..build_pack() });
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].to_string(), "enemies.json (id 1): duplicate id");
+    }
+
+    #[test]
+    fn test_dangling_references() {
+        let problems = validate(&DataPack { levels: build_levels(), rooms: build_rooms(), ..DataPack::default() });
+        let messages: Vec<String> = problems.iter().map(|problem| problem.to_string()).collect();
+        assert_eq!(messages, vec![
+            "rooms.json (id 1): references missing item 1",
+            "rooms.json (id 2): references missing encounter 1",
+        ]);
+    }
+
+    #[test]
+    fn test_invalid_range_and_template() {
+        let encounters: Vec<Encounter> = parse(r#"[{ "id": 1, "text": "", "enemies": [[1, {"Range": [4, 2]}]] }]"#);
+        let mut enemies = build_enemies();
+        enemies[0].template = "dragon".to_string();
+
+        let problems = validate(&DataPack { encounters, enemies, ..build_pack() });
+        let messages: Vec<String> = problems.iter().map(|problem| problem.to_string()).collect();
+        assert_eq!(messages, vec![
+            "encounters.json (id 1): invalid range 4-2 for enemy 1",
+            "enemies.json (id 1): unknown template dragon",
+        ]);
+    }
+
+    #[test]
+    fn test_damage_ranges() {
+        let rooms: Vec<Room> = parse(r#"[
+            { "id": 1, "title": "A", "text": "", "choices": [{ "text": "jump", "consequences": [{ "t": "AbilityCheck", "c": ["Agil", 3] }] }] },
+            { "id": 2, "title": "B", "text": "", "choices": [] }
+        ]"#);
+
+        let problems = validate(&DataPack { levels: build_levels(), rooms: rooms.clone(), ..DataPack::default() });
+        assert_eq!(problems[0].to_string(), "rooms.json (id 1): no damage range for difficulty 3");
+
+        let damage: Vec<DamageRange> = parse(r#"[{ "dificulty": 1, "damage": "1d4-2" }, { "dificulty": 1, "damage": "1d4" }]"#);
+        let problems = validate(&DataPack { levels: build_levels(), rooms, damage, ..DataPack::default() });
+        let messages: Vec<String> = problems.iter().map(|problem| problem.to_string()).collect();
+        assert_eq!(messages, vec![
+            "damage.json (id 1): duplicate id",
+            "damage.json (id 1): damage 1d4-2 can be negative",
+        ]);
+    }
+
+    #[test]
+    fn test_classes() {
+        let classes: Vec<CharacterClass> = parse(r#"[
+            { "id": 1, "name": "Warrior", "hp_max": 110, "strg": 2, "agil": 1, "def": 2, "items": [1],
+              "attacks": [{ "title": "Cleave", "attack_type": "physical", "dmg_min": 2, "dmg_max": 5 }] },
+            { "id": 2, "name": "Giant", "hp_max": 200, "strg": 6, "agil": 1, "def": 3, "skills": [4],
+              "attacks": [{ "title": "Stomp", "attack_type": "physical", "dmg_min": 5, "dmg_max": 2 }] }
+        ]"#);
+
+        let problems = validate(&DataPack { classes, ..build_pack() });
+        let messages: Vec<String> = problems.iter().map(|problem| problem.to_string()).collect();
+        assert_eq!(messages, vec![
+            "classes.json (id 2): references missing skill 4",
+            "classes.json (id 2): abilities have to be between 1 and 5",
+            "classes.json (id 2): abilities add up to 10, more than the budget of 8",
+            "classes.json (id 2): invalid damage 5-2 of attack Stomp",
+        ]);
+    }
+
+    #[test]
+    fn test_item_attacks() {
+        let items: Vec<Item> = parse(r#"[
+            { "id": 1, "name": "Sword", "item_type": "Weapon",
+              "attacks": [{ "title": "Slash", "attack_type": "physical", "dmg_min": 6, "dmg_max": 2 }] },
+            { "id": 2, "name": "Spiked Armor", "item_type": "Armor",
+              "attacks": [{ "title": "Spikes", "attack_type": "physical", "dmg_min": 1, "dmg_max": 2 }] }
+        ]"#);
+
+        let problems = validate(&DataPack { items, ..build_pack() });
+        let messages: Vec<String> = problems.iter().map(|problem| problem.to_string()).collect();
+        assert_eq!(messages, vec![
+            "items.json (id 1): invalid damage 6-2 of attack Slash",
+            "items.json (id 2): only weapons can have attacks",
+        ]);
+    }
+
+    #[test]
+    fn test_status_effects() {
+        let items: Vec<Item> = parse(r#"[
+            { "id": 1, "name": "Dagger", "item_type": "Weapon", "attacks": [{ "title": "Cut", "attack_type": "physical",
+              "dmg_min": 1, "dmg_max": 2, "status_effects": [{ "kind": "Bleed", "turns": 2 }] }] },
+            { "id": 2, "name": "Flash Powder", "item_type": "Consumable",
+              "effects": [{ "t": "Status", "c": { "kind": "Stun", "turns": 0 } }] },
+            { "id": 3, "name": "Smoke Bomb", "item_type": "Consumable",
+              "effects": [{ "t": "Status", "c": { "kind": "Stun", "turns": 1 } }] }
+        ]"#);
+
+        let problems = validate(&DataPack { items, ..build_pack() });
+        let messages: Vec<String> = problems.iter().map(|problem| problem.to_string()).collect();
+        assert_eq!(messages, vec![
+            "items.json (id 1): attack Cut: status effect Bleed needs an amount",
+            "items.json (id 2): status effect Stun lasts no turn",
+        ]);
+    }
+
+    #[test]
+    fn test_item_effects() {
+        let items: Vec<Item> = parse(r#"[
+            { "id": 1, "name": "Potion", "item_type": "Consumable", "effects": [{ "t": "Heal", "c": "1d6-2" }] },
+            { "id": 2, "name": "Empty Bottle", "item_type": "Consumable" },
+            { "id": 3, "name": "Sword", "item_type": "Weapon", "effects": [{ "t": "Cure" }] }
+        ]"#);
+
+        let problems = validate(&DataPack { items, ..build_pack() });
+        let messages: Vec<String> = problems.iter().map(|problem| problem.to_string()).collect();
+        assert_eq!(messages, vec![
+            "items.json (id 1): effect Heal 1d6-2 can be negative",
+            "items.json (id 2): consumables need at least one effect",
+            "items.json (id 3): only consumables can have effects",
+        ]);
+    }
+}
@@ -0,0 +1,395 @@
+//! Dice expressions like `3d6+2`, `2d20kh1` or the success pool `5d6>=4`.
+//!
+//! A sum expression adds up dice terms and constants. `kh` and `kl` only keep the highest or
+//! lowest dice of a term. A success pool rolls its dice and counts every die reaching the target.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+use crate::random::GameRng;
+
+/// The most dice a single term may roll, keeps every total far within an `i32`.
+pub const MAX_DICE_COUNT: u16 = 100;
+/// The most sides a die may have.
+pub const MAX_DICE_SIDES: u16 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Keep {
+    All,
+    Highest(u16),
+    Lowest(u16),
+}
+
+/// `count` dice with `sides` sides each.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dice {
+    pub count: u16,
+    pub sides: u16,
+    pub keep: Keep,
+}
+
+impl Dice {
+    pub fn new(count: u16, sides: u16) -> Dice {
+        Dice { count, sides, keep: Keep::All }
+    }
+
+    pub fn roll(&self, rng: &mut GameRng) -> Vec<u16> {
+        (0..self.count).map(|_| rng.gen_range(1..=self.sides)).collect()
+    }
+
+    /// The sum of the kept dice.
+    pub fn sum_kept(&self, rolls: &[u16]) -> i32 {
+        let mut sorted = rolls.to_vec();
+        sorted.sort_unstable();
+
+        let kept = match self.keep {
+            Keep::All => &sorted[..],
+            Keep::Highest(amount) => &sorted[sorted.len().saturating_sub(amount as usize)..],
+            Keep::Lowest(amount) => &sorted[..sorted.len().min(amount as usize)],
+        };
+
+        kept.iter().map(|roll| *roll as i32).sum()
+    }
+
+    fn kept_count(&self) -> i32 {
+        let count = match self.keep {
+            Keep::All => self.count,
+            Keep::Highest(amount) | Keep::Lowest(amount) => amount.min(self.count),
+        };
+        count as i32
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Term {
+    Dice(Dice),
+    Constant(i32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiceExpression {
+    /// Added up terms, the sign is stored with every term.
+    Sum(Vec<(i32, Term)>),
+    /// Counts the dice reaching `target`.
+    Pool { dice: Dice, target: u16 },
+}
+
+/// Every die rolled for an expression and the resulting total.
+/// The total of a success pool is the amount of successes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiceRoll {
+    pub rolls: Vec<u16>,
+    pub total: i32,
+}
+
+impl DiceExpression {
+    /// A pool of d6 succeeding on `target` or higher, as used by ability checks.
+    pub fn d6_pool(count: u16, target: u8) -> DiceExpression {
+        DiceExpression::Pool { dice: Dice::new(count, 6), target: target.into() }
+    }
+
+    pub fn roll(&self, rng: &mut GameRng) -> DiceRoll {
+        match self {
+            DiceExpression::Sum(terms) => {
+                let mut rolls = Vec::new();
+                let mut total = 0;
+                for (sign, term) in terms {
+                    match term {
+                        Term::Dice(dice) => {
+                            let dice_rolls = dice.roll(rng);
+                            total += sign * dice.sum_kept(&dice_rolls);
+                            rolls.extend(dice_rolls);
+                        }
+                        Term::Constant(value) => total += sign * value,
+                    }
+                }
+                DiceRoll { rolls, total }
+            }
+            DiceExpression::Pool { dice, target } => {
+                let rolls = dice.roll(rng);
+                let total = rolls.iter().filter(|roll| *roll >= target).count() as i32;
+                DiceRoll { rolls, total }
+            }
+        }
+    }
+
+    /// Rolls an amount of hp, see `to_amount`.
+    pub fn roll_amount(&self, rng: &mut GameRng) -> u16 {
+        DiceExpression::to_amount(self.roll(rng).total)
+    }
+
+    /// Clamps a total to an amount of hp. Negative totals are no amount at all,
+    /// totals too large for a `u16` are the largest amount.
+    pub fn to_amount(total: i32) -> u16 {
+        u16::try_from(total.max(0)).unwrap_or(u16::MAX)
+    }
+
+    pub fn min(&self) -> i32 {
+        self.bounds().0
+    }
+
+    pub fn max(&self) -> i32 {
+        self.bounds().1
+    }
+
+    fn bounds(&self) -> (i32, i32) {
+        match self {
+            DiceExpression::Sum(terms) => terms.iter()
+                .map(|(sign, term)| {
+                    let (low, high) = match term {
+                        Term::Dice(dice) => (dice.kept_count(), dice.kept_count() * dice.sides as i32),
+                        Term::Constant(value) => (*value, *value),
+                    };
+                    if *sign < 0 { (-high, -low) } else { (low, high) }
+                })
+                .fold((0, 0), |(min, max), (low, high)| (min + low, max + high)),
+            DiceExpression::Pool { dice, target } => {
+                let max = if *target > dice.sides { 0 } else { dice.count as i32 };
+                (0, max)
+            }
+        }
+    }
+}
+
+impl FromStr for DiceExpression {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<DiceExpression, Error> {
+        let invalid = |reason: &str| Error::GameDataError(format!("invalid dice expression {}: {}", text, reason));
+
+        let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase();
+        if compact.is_empty() {
+            return Err(invalid("empty"));
+        }
+
+        if let Some(index) = compact.find('>') {
+            let (dice, target) = compact.split_at(index);
+            let target = match target.strip_prefix(">=") {
+                Some(target) => parse_number(target).ok_or_else(|| invalid("target is not a number"))?,
+                None => parse_number(&target[1..]).ok_or_else(|| invalid("target is not a number"))? + 1,
+            };
+            let dice = parse_dice(dice).map_err(invalid)?;
+            if dice.keep != Keep::All {
+                return Err(invalid("a success pool can not keep dice"));
+            }
+            return Ok(DiceExpression::Pool { dice, target });
+        }
+
+        let mut terms = Vec::new();
+        let mut sign = 1;
+        let mut start = 0;
+        for (index, c) in compact.char_indices().chain(std::iter::once((compact.len(), '+'))) {
+            if c != '+' && c != '-' {
+                continue;
+            }
+            let term = &compact[start..index];
+            if term.is_empty() {
+                return Err(invalid("missing term"));
+            }
+            terms.push((sign, parse_term(term).map_err(invalid)?));
+            sign = if c == '-' { -1 } else { 1 };
+            start = index + 1;
+        }
+
+        Ok(DiceExpression::Sum(terms))
+    }
+}
+
+fn parse_number(text: &str) -> Option<u16> {
+    if text.is_empty() || !text.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+fn parse_term(text: &str) -> Result<Term, &'static str> {
+    if text.contains('d') {
+        parse_dice(text).map(Term::Dice)
+    } else {
+        parse_number(text).map(|value| Term::Constant(value.into())).ok_or("term is not a number")
+    }
+}
+
+fn parse_dice(text: &str) -> Result<Dice, &'static str> {
+    let (count, rest) = text.split_at(text.find('d').ok_or("missing d")?);
+    let count = if count.is_empty() { 1 } else { parse_number(count).ok_or("dice count is not a number")? };
+    if count > MAX_DICE_COUNT {
+        return Err("more than 100 dice");
+    }
+
+    let (sides, keep) = match rest[1..].find('k') {
+        Some(index) => {
+            let (sides, keep) = rest[1..].split_at(index);
+            let keep = match (keep.get(..2), keep.get(2..).and_then(parse_number)) {
+                (Some("kh"), Some(amount)) => Keep::Highest(amount),
+                (Some("kl"), Some(amount)) => Keep::Lowest(amount),
+                _ => return Err("keep needs kh or kl and an amount"),
+            };
+            (sides, keep)
+        }
+        None => (&rest[1..], Keep::All),
+    };
+
+    let sides = parse_number(sides).ok_or("sides are not a number")?;
+    if sides == 0 {
+        return Err("dice need at least one side");
+    }
+    if sides > MAX_DICE_SIDES {
+        return Err("dice have at most 1000 sides");
+    }
+
+    Ok(Dice { count, sides, keep })
+}
+
+impl fmt::Display for Dice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}d{}", self.count, self.sides)?;
+        match self.keep {
+            Keep::All => Ok(()),
+            Keep::Highest(amount) => write!(f, "kh{}", amount),
+            Keep::Lowest(amount) => write!(f, "kl{}", amount),
+        }
+    }
+}
+
+impl fmt::Display for DiceExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiceExpression::Sum(terms) => {
+                for (index, (sign, term)) in terms.iter().enumerate() {
+                    if *sign < 0 {
+                        write!(f, "-")?;
+                    } else if index > 0 {
+                        write!(f, "+")?;
+                    }
+                    match term {
+                        Term::Dice(dice) => write!(f, "{}", dice)?,
+                        Term::Constant(value) => write!(f, "{}", value)?,
+                    }
+                }
+                Ok(())
+            }
+            DiceExpression::Pool { dice, target } => write!(f, "{}>={}", dice, target),
+        }
+    }
+}
+
+impl Serialize for DiceExpression {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for DiceExpression {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<DiceExpression, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::new_rng;
+
+    const SAMPLES: usize = 20_000;
+
+    fn parse(text: &str) -> DiceExpression {
+        text.parse().unwrap()
+    }
+
+    fn average(expression: &DiceExpression, seed: u64) -> f64 {
+        let mut rng = new_rng(seed);
+        let sum: i32 = (0..SAMPLES).map(|_| expression.roll(&mut rng).total).sum();
+        sum as f64 / SAMPLES as f64
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("3d6+2"), DiceExpression::Sum(vec![(1, Term::Dice(Dice::new(3, 6))), (1, Term::Constant(2))]));
+        assert_eq!(parse("d20").to_string(), "1d20");
+        assert_eq!(parse("2D20 kh1").to_string(), "2d20kh1");
+        assert_eq!(parse("4d6kl3-1d4-1").to_string(), "4d6kl3-1d4-1");
+        assert_eq!(parse("5d6>=5"), DiceExpression::d6_pool(5, 5));
+        assert_eq!(parse("5d6>4"), DiceExpression::d6_pool(5, 5));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for text in &["", "3d", "d0", "3d6+", "+3", "2d20kh", "2d20kx1", "3x6", "4d6kh3>=4", "3d6>=x"] {
+            assert!(text.parse::<DiceExpression>().is_err(), "{} should not parse", text);
+        }
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        assert_eq!(parse("100d1000").max(), 100_000);
+        for text in &["101d6", "1d1001", "65535d65535", "65535d65535>=2"] {
+            assert!(text.parse::<DiceExpression>().is_err(), "{} should not parse", text);
+        }
+    }
+
+    #[test]
+    fn test_bounds() {
+        assert_eq!((parse("3d6+2").min(), parse("3d6+2").max()), (5, 20));
+        assert_eq!((parse("2d20kh1").min(), parse("2d20kh1").max()), (1, 20));
+        assert_eq!((parse("2d6-1d4").min(), parse("2d6-1d4").max()), (-2, 11));
+        assert_eq!((parse("4d6>=4").min(), parse("4d6>=4").max()), (0, 4));
+    }
+
+    #[test]
+    fn test_serde() {
+        let expression: DiceExpression = serde_json::from_str(r#""2d6+3""#).unwrap();
+        assert_eq!(serde_json::to_string(&expression).unwrap(), r#""2d6+3""#);
+        assert!(serde_json::from_str::<DiceExpression>(r#""2d""#).is_err());
+    }
+
+    #[test]
+    fn test_d6_faces_are_uniform() {
+        let mut rng = new_rng(1);
+        let mut counts = [0usize; 7];
+        for roll in Dice::new(SAMPLES as u16, 6).roll(&mut rng) {
+            counts[roll as usize] += 1;
+        }
+
+        assert_eq!(counts[0], 0);
+        for (face, count) in counts.iter().enumerate().skip(1) {
+            let share = *count as f64 / SAMPLES as f64;
+            assert!((share - 1.0 / 6.0).abs() < 0.01, "face {} rolled {:.3}", face, share);
+        }
+    }
+
+    #[test]
+    fn test_averages() {
+        assert!((average(&parse("3d6+2"), 2) - 12.5).abs() < 0.1);
+        // the higher of two d20 averages 13.825
+        assert!((average(&parse("2d20kh1"), 3) - 13.825).abs() < 0.15);
+        assert!((average(&parse("2d20kl1"), 4) - 7.175).abs() < 0.15);
+        // every die of the pool succeeds with a chance of one half
+        assert!((average(&parse("4d6>=4"), 5) - 2.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_to_amount() {
+        assert_eq!(DiceExpression::to_amount(-3), 0);
+        assert_eq!(DiceExpression::to_amount(12), 12);
+        assert_eq!(DiceExpression::to_amount(70000), u16::MAX, "no wrap around");
+    }
+
+    #[test]
+    fn test_rolls_stay_in_bounds() {
+        let mut rng = new_rng(6);
+        for text in &["3d6+2", "2d20kh1", "2d6-1d4", "6d6>=6"] {
+            let expression = parse(text);
+            for _ in 0..1000 {
+                let total = expression.roll(&mut rng).total;
+                assert!(total >= expression.min() && total <= expression.max(), "{} rolled {}", text, total);
+            }
+        }
+    }
+}
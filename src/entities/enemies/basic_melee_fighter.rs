@@ -2,27 +2,38 @@ use crate::models::Actor;
 use crate::models::BattleEvent;
 use crate::models::attack_methods::AttackFunction;
 use crate::models::ActorValues;
+use crate::models::attack_options::AttackDescription;
+use crate::damage::attack;
+use crate::random::GameRng;
 
 pub struct BasicMeleeFighter {
     pub values: ActorValues,
 }
 
+impl BasicMeleeFighter {
+    pub fn create(values: ActorValues) -> Box<dyn Actor> {
+        Box::new(BasicMeleeFighter { values })
+    }
+}
+
 impl Actor for BasicMeleeFighter {
     fn get_actor_values(&self) -> ActorValues {
         self.values.clone()
     }
-    fn selectAttack(&self) -> Box<AttackFunction> {
+    fn select_attack(&self) -> Box<AttackFunction> {
         Box::new(melee_attack)
     }
 }
 
-fn melee_attack(user: &mut ActorValues, target: &mut ActorValues) -> BattleEvent {
-    let dmg = 0;
-
-    BattleEvent {
+fn melee_attack(rng: &mut GameRng, user: &mut ActorValues, target: &mut ActorValues) -> BattleEvent {
+    let description = AttackDescription {
         title: "Melee Attack".to_owned(),
-        source: user.name.to_owned(),
-        target: target.name.to_owned(),
-        effect: format!("{} lost {} hp", target.name, dmg)
-    }
-}
\ No newline at end of file
+        attack_type: "physical".to_owned(),
+        dmg_min: 1,
+        dmg_max: 4,
+        special_effect: "".to_owned(),
+        status_effects: Vec::new(),
+    };
+
+    attack(rng, &description, user, target)
+}
@@ -1 +1,4 @@
-pub mod enemies;
\ No newline at end of file
+pub mod enemies;
+mod registry;
+
+pub use registry::*;
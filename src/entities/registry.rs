@@ -0,0 +1,35 @@
+use crate::Error;
+use crate::models::ActorConstructor;
+use crate::entities::enemies::BasicMeleeFighter;
+
+/// All enemy templates which can be referenced by `Enemy.template`.
+/// New templates only need to be added here.
+const TEMPLATES: &[(&str, ActorConstructor)] = &[
+    ("basic_melee_fighter", BasicMeleeFighter::create),
+];
+
+pub fn find_template(name: &str) -> Result<ActorConstructor, Error> {
+    match TEMPLATES.iter().find(|(template, _)| *template == name) {
+        Some((_, constructor)) => Ok(*constructor),
+        None => Err(Error::GameDataError(format!("Unknown enemy template: {}", name))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ ActorValues, Enemy };
+
+    #[test]
+    fn test_find_template() {
+        let constructor = find_template("basic_melee_fighter").unwrap();
+        let values: ActorValues = (&Enemy::new(1, "Peter")).into();
+        let actor = constructor(values);
+        assert_eq!(actor.get_actor_values().name, "Peter");
+    }
+
+    #[test]
+    fn test_unknown_template() {
+        assert!(find_template("dragon").is_err());
+    }
+}
@@ -7,6 +7,10 @@ pub enum Error {
     ReadDBError(#[from] io::Error),
     #[error("error parsing the DB file: {0}")]
     ParseDBError(#[from] serde_json::Error),
-    #[error("error invalid game data")]
+    #[error("error invalid game data: {0}")]
     GameDataError(String),
+    #[error("error with the save game: {0}")]
+    SaveGameError(String),
+    #[error("error invalid character: {0}")]
+    CharacterError(String),
 }
@@ -1,31 +1,91 @@
-use crate::battle_handler::BattleHandler;
-use crate::models::models::{ Reward, RewardType, Ability, BadResult, BadResultType };
-use crate::state::DungeonState;
+use crate::battle_handler::{ BattleHandler, BattleOutcome };
+use crate::models::models::{ Reward, RewardType, Ability, BadResult, BadResultType, CheckResult, Character, CharacterClass, ItemEffect, ItemType, RoomResult };
+use crate::models::attack_options::AttackOption;
+use crate::models::{ Enemy, EnemyDeclaration };
+use crate::state::{ DungeonState, SaveGame };
 use crate::Error;
-use std::sync::Mutex;
-use std::sync::Arc;
+use std::sync::{ Arc, Mutex, MutexGuard };
+use std::rc::Rc;
 
 use rand::prelude::*;
 
 use crate::data::GameData;
+use crate::dice::DiceExpression;
 use crate::state::GameState;
 use crate::models::models::RoomResult::*;
 
-use crate::random::ability_check_with_nd6;
+use crate::random::{ ability_check_with_nd6, new_rng, CheckOutcome, CheckRoll, GameRng };
+use crate::replay::{ Command, Replay };
+use crate::leveling::gain_xp;
+
+/// Everything that happened while executing a room choice.
+#[derive(Default)]
+struct ChoiceOutcome {
+    rewards: Vec<Reward>,
+    bad_results: Vec<BadResult>,
+    checks: Vec<CheckResult>,
+    next_room: Option<u16>,
+}
 
 pub struct GameHandler {
     game_data: GameData,
     game_state: Arc<Mutex<GameState>>,
     battle_handler: Arc<Mutex<BattleHandler>>,
+    seed: u64,
+    rng: GameRng,
+    replay: Replay,
+    /// The character every run starts with.
+    character: Character,
+    /// The follow up room of the choice which started the current fight, entered after a victory.
+    next_room_after_battle: Option<u16>,
 }
 
 impl GameHandler {
+    /// Creates a game with a random seed.
     pub fn new(game_data: GameData) -> GameHandler {
-        let state = Arc::new(Mutex::new(GameState::new()));
+        GameHandler::with_seed(game_data, thread_rng().gen())
+    }
+
+    /// Creates a game whose randomness is fully determined by the seed.
+    /// The same seed and the same inputs always result in the same run.
+    pub fn with_seed(game_data: GameData, seed: u64) -> GameHandler {
+        let mut state = GameState::new();
+        state.seed = seed;
+
         GameHandler { 
             game_data: game_data,
-            game_state: state,
+            game_state: Arc::new(Mutex::new(state)),
             battle_handler: Arc::new(Mutex::new(BattleHandler::new())),
+            seed,
+            rng: new_rng(seed),
+            replay: Replay::new(seed),
+            character: Character::default(),
+            next_room_after_battle: None,
+        }
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// All commands executed since the game was created.
+    pub fn get_replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn execute_command(&mut self, command: Command) -> Result<(), Error> {
+        match command {
+            Command::RoomChoice(index) => self.execute_room_choice(index),
+            Command::BattleAction(index) => self.execute_battle_action(index),
+            Command::EquipItem(index) => self.equip_item_by_index(index),
+            Command::ConfirmResult => {
+                self.confirm_result();
+                Ok(())
+            }
+            Command::ResetGame => self.reset_game(),
+            Command::SpendStatPoint(ability) => self.spend_stat_point(ability),
+            Command::NewGame(character) => self.new_game(character),
+            Command::UseItem(index) => self.use_item(index),
         }
     }
 
@@ -34,10 +94,60 @@ impl GameHandler {
     }
 
     pub fn start_game(&mut self) -> Result<(), Error> {
-        let level = self.game_data.find_level_by_id(1)?;
-        let mut state = self.game_state.lock().unwrap();
+        let level_id = self.game_data.find_first_level()?.id;
+        self.state().character = self.character.clone();
+        self.apply_character_class()?;
+        self.start_level(level_id)
+    }
+
+    /// Hands out the starting items and skills of the class of the character. Weapons and armor are equipped.
+    fn apply_character_class(&mut self) -> Result<(), Error> {
+        let class = match self.character.class {
+            Some(class_id) => self.game_data.find_class_by_id(class_id)?,
+            None => return Ok(()),
+        };
+        self.state().character_class = Some(class.clone());
+
+        for id in &class.items {
+            self.gain_item(id)?;
+            if self.game_data.find_item_by_id(*id)?.item_type != ItemType::Consumable {
+                self.equip_item(*id)?;
+            }
+        }
+        for id in &class.skills {
+            self.gain_skill_once(id)?;
+        }
+
+        Ok(())
+    }
+
+    /// The classes a new character can pick from.
+    pub fn get_classes(&self) -> &[Rc<CharacterClass>] {
+        self.game_data.get_classes()
+    }
+
+    /// Starts the game with a newly created character.
+    /// Before the first run this starts the game, afterwards it starts a new run like `reset_game`.
+    pub fn new_game(&mut self, character: Character) -> Result<(), Error> {
+        if self.state().current_level.is_none() {
+            self.replay.character = Some(character.clone());
+            self.character = character;
+            return self.start_game();
+        }
+
+        self.replay.commands.push(Command::NewGame(character.clone()));
+        self.character = character;
+        self.restart()
+    }
+
+    /// Enters the first room of the level with the given id.
+    pub fn start_level(&self, level_id: u16) -> Result<(), Error> {
+        let level = self.game_data.find_level_by_id(level_id)?;
+        let mut state = self.state();
 
         state.set_current_level(level);
+        state.level_points = 0;
+        state.final_room_entered = false;
 
         let room_id = level.first_room;
         let room = self.game_data.find_room_by_id(room_id)?;
@@ -46,117 +156,357 @@ impl GameHandler {
         Ok(())
     }
 
+    /// Moves on to the next level of the campaign or ends the run if there is none.
+    fn complete_level(&self) -> Result<(), Error> {
+        let level = self.state().get_current_level()?;
+
+        match level.next_level {
+            Some(level_id) => self.start_level(level_id)?,
+            None => self.change_dungeon_state(DungeonState::CampaignComplete),
+        }
+
+        Ok(())
+    }
+
+    /// Starts a new run. The seed of the new run is drawn from the current one.
     pub fn reset_game(&mut self) -> Result<(), Error> {
+        self.replay.commands.push(Command::ResetGame);
+        self.restart()
+    }
+
+    fn restart(&mut self) -> Result<(), Error> {
+        self.seed = self.rng.gen();
+        self.rng = new_rng(self.seed);
+
         let mut state = self.game_state.lock().expect("Could not lock game_State");
         *state = GameState::new();
+        state.seed = self.seed;
         drop(state);
         self.start_game()?;
         Ok(())
     }
 
+    pub fn save_game(&self, path: &str) -> Result<(), Error> {
+        let gs = self.state();
+        if let DungeonState::Encounter = gs.dungeon_state {
+            return Err(Error::SaveGameError("Can not save during a battle.".to_string()));
+        }
+
+        SaveGame::from(&*gs).write_to_file(path)
+    }
+
+    /// Continues the saved run with a seed drawn from the current one.
+    /// The recording starts over from the save game, the commands before the load don't lead to it.
+    pub fn load_game(&mut self, path: &str) -> Result<(), Error> {
+        let save = SaveGame::read_from_file(path)?;
+        // Only a successful load moves on to the new seed.
+        let seed = self.rng.clone().gen();
+        self.continue_from(save, seed, self.character.clone())
+    }
+
+    /// Continues the saved run with the seed, new runs start with the character.
+    pub(crate) fn continue_from(&mut self, save: SaveGame, seed: u64, character: Character) -> Result<(), Error> {
+        let mut state = save.clone().into_game_state(&self.game_data)?;
+        state.seed = seed;
+        *self.state() = state;
+
+        self.seed = seed;
+        self.rng = new_rng(seed);
+        self.replay = Replay { seed, character: Some(character.clone()), save: Some(save), commands: Vec::new() };
+        self.character = character;
+        Ok(())
+    }
+
     pub fn execute_room_choice(&mut self, index: usize) -> Result<(), Error> {
+        let state = self.get_dungeon_state();
+        if state != DungeonState::Room {
+            return Err(Error::GameDataError(format!("Can not execute a room choice in the {:?} state.", state)));
+        }
+
+        self.apply_room_choice(index)?;
+        self.replay.commands.push(Command::RoomChoice(index));
+        Ok(())
+    }
 
-        let choices = match &self.game_state.lock().unwrap().current_room {
+    fn apply_room_choice(&mut self, index: usize) -> Result<(), Error> {
+        let choices = match &self.state().current_room {
             Some(room) => room.choices.clone(),
             None => return Err(Error::GameDataError(format!("Cant execute choices, no current room set."))),
         };
 
-        let mut rewards: Vec<Reward> = Vec::new();
-        let mut bad_results: Vec<BadResult> = Vec::new();
-
-        if choices.len() > index {
-            let cons = &choices[index].consequences;
-            for c in cons {
-                match c {
-                    GainLevelPoints(points) => {
-                        self.increase_level_points(points);
-                    },
-                    GainXp(xp) => {
-                        self.increase_xp(xp);
-
-                        rewards.push(Reward {
-                            reward_type: RewardType::Xp,
-                            name: "Experience Points".to_string(),
-                            amount: *xp as usize,
-                        });
-                    },
-                    GainItem(id) => {
-                        self.gain_item(id)?;
+        let mut gs = self.state();
+        gs.last_bad_results.clear();
+        gs.last_rewards.clear();
+        gs.last_checks.clear();
+        drop(gs);
 
-                        let item = self.game_data.find_item_by_id(*id)?;
-                        let item_type = item.item_type.clone();
+        let mut outcome = ChoiceOutcome::default();
 
-                        rewards.push(Reward {
-                            reward_type: RewardType::Item(item_type),
-                            name: item.name.to_owned(),
-                            amount: 1,
-                        });
-                    },
-                    GainSkill(id) => {
-                        // gain skill
-                        let gained = self.gain_skill_once(id)?;
-
-                        if gained {
-                            let skill = self.game_data.find_skill_by_id(*id)?;
-
-                            rewards.push(Reward {
-                                reward_type: RewardType::Skill,
-                                name: skill.name.to_owned(),
-                                amount: 1,
-                            });
-                        }
-                    },
-                    StartFight(_id) => {
-                        self.change_dungeon_state(DungeonState::Encounter);
-                    },
-                    AbilityCheck(ability, dificulty) => {
-                        let success = self.ability_check(ability.clone(), *dificulty)?;
-                        if !success {
-                            let damage = self.recive_damage(*dificulty);
-                            bad_results.push(BadResult {
-                                bad_result_type: BadResultType::Damage,
-                                name: "Damage".to_string(),
-                                amount: damage.into(),
-                            });
-
-                            self.change_dungeon_state(DungeonState::Failure);
-                            break;
-                        }
-                    },
+        if let Some(choice) = choices.get(index) {
+            outcome.next_room = choice.next;
+
+            if choice.has_outcomes() {
+                let completed = self.apply_consequences(&choice.consequences, false, &mut outcome)?;
+                let worst = outcome.checks.iter()
+                    .map(|check| check.roll.outcome)
+                    .min()
+                    .unwrap_or(CheckOutcome::Success);
+                if completed {
+                    self.apply_consequences(choice.consequences_for(worst), true, &mut outcome)?;
                 }
-            }  
+            } else {
+                self.apply_consequences(&choice.consequences, true, &mut outcome)?;
+            }
         }
 
-        if rewards.len() > 0 {
+        let state = self.get_dungeon_state();
+        let in_battle = matches!(state, DungeonState::Encounter);
+        let game_over = matches!(state, DungeonState::GameOver);
+        let has_news = !outcome.rewards.is_empty() || !outcome.checks.is_empty();
+        if has_news && !in_battle && !game_over && !matches!(state, DungeonState::Failure) {
             self.change_dungeon_state(DungeonState::Result);
         }
 
-        let mut gs = self.game_state.lock().unwrap();
+        let mut gs = self.state();
 
-        gs.last_bad_results = bad_results;
-        gs.last_rewards = rewards;
+        gs.last_bad_results.append(&mut outcome.bad_results);
+        gs.last_rewards.append(&mut outcome.rewards);
+        gs.last_checks.append(&mut outcome.checks);
         drop(gs);
 
-        self.change_room()?;
+        if game_over {
+            return Ok(());
+        }
+        // The room of a choice which started a fight stays until the fight is won.
+        if in_battle {
+            self.next_room_after_battle = outcome.next_room;
+            return Ok(());
+        }
+
+        self.change_room(outcome.next_room)?;
+
+        Ok(())
+    }
+
+    /// Executes the consequences in order. Returns false if the execution stopped early,
+    /// either because a fight started, the player died or a failed ability check is set to `stop_on_failure`.
+    /// A partial success only counts as a failure when stopping on failures.
+    fn apply_consequences(&mut self, consequences: &[RoomResult], stop_on_failure: bool, outcome: &mut ChoiceOutcome) -> Result<bool, Error> {
+        for c in consequences {
+            match c {
+                GainLevelPoints(points) => {
+                    self.increase_level_points(points);
+
+                    outcome.rewards.push(Reward {
+                        reward_type: RewardType::LevelPoints,
+                        name: "Level Points".to_string(),
+                        amount: *points as usize,
+                    });
+                },
+                GainXp(xp) => {
+                    let levels = self.increase_xp(xp);
+
+                    outcome.rewards.push(Reward {
+                        reward_type: RewardType::Xp,
+                        name: "Experience Points".to_string(),
+                        amount: *xp as usize,
+                    });
+
+                    if levels > 0 {
+                        let level = self.state().character.level;
+                        outcome.rewards.push(Reward {
+                            reward_type: RewardType::LevelUp,
+                            name: format!("Level Up! You reached level {}", level),
+                            amount: 1,
+                        });
+                    }
+                },
+                GainItem(id) => {
+                    self.gain_item(id)?;
+
+                    let item = self.game_data.find_item_by_id(*id)?;
+                    let item_type = item.item_type.clone();
+
+                    outcome.rewards.push(Reward {
+                        reward_type: RewardType::Item(item_type),
+                        name: item.name.to_owned(),
+                        amount: 1,
+                    });
+                },
+                GainSkill(id) => {
+                    // gain skill
+                    let gained = self.gain_skill_once(id)?;
+
+                    if gained {
+                        let skill = self.game_data.find_skill_by_id(*id)?;
+
+                        outcome.rewards.push(Reward {
+                            reward_type: RewardType::Skill,
+                            name: skill.name.to_owned(),
+                            amount: 1,
+                        });
+                    }
+                },
+                StartFight(id) => {
+                    let enemies = self.spawn_encounter(*id)?;
+                    self.start_battle(enemies)?;
+                    return Ok(false);
+                },
+                AbilityCheck(ability, dificulty) => {
+                    let roll = self.ability_check(ability.clone(), *dificulty)?;
+                    let failed = match roll.outcome {
+                        CheckOutcome::PartialSuccess => stop_on_failure,
+                        result => !result.is_success(),
+                    };
+                    let mut check = CheckResult {
+                        ability: ability.clone(),
+                        dificulty: *dificulty,
+                        roll,
+                        damage: 0,
+                    };
+
+                    if failed {
+                        self.change_dungeon_state(DungeonState::Failure);
+
+                        let fumble = check.roll.outcome == CheckOutcome::Fumble;
+                        let (damage, damage_type) = self.recive_damage(*dificulty, fumble)?;
+                        check.damage = damage;
+                        outcome.bad_results.push(BadResult {
+                            bad_result_type: BadResultType::Damage,
+                            name: damage_type.unwrap_or_else(|| "Damage".to_string()),
+                            amount: damage.into(),
+                        });
+                    }
+                    outcome.checks.push(check);
+
+                    if failed && (stop_on_failure || matches!(self.get_dungeon_state(), DungeonState::GameOver)) {
+                        return Ok(false);
+                    }
+                },
+                NextRoom(id) => {
+                    outcome.next_room = Some(*id);
+                },
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Creates the enemy instances declared by the encounter with the given id.
+    pub fn spawn_encounter(&mut self, encounter_id: u16) -> Result<Vec<Enemy>, Error> {
+        let encounter = self.game_data.find_encounter_by_id(encounter_id)?;
+        let mut enemies = Vec::new();
+
+        for EnemyDeclaration(enemy_id, amount) in &encounter.enemies {
+            let enemy = self.game_data.find_enemy_by_id(*enemy_id)?;
+            let amount = amount.roll(&mut self.rng);
+            for number in 1..=amount {
+                let number = if amount > 1 { Some(number) } else { None };
+                enemies.push(enemy.spawn(number));
+            }
+        }
+
+        Ok(enemies)
+    }
+
+    /// Starts a fresh battle of the player against the given enemies.
+    /// Enemies faster than the player act right away.
+    pub fn start_battle(&mut self, enemies: Vec<Enemy>) -> Result<(), Error> {
+        let character = self.state().get_effective_character();
+
+        let mut battle = enemies.iter()
+            .fold(BattleHandler::new().add_player(&character), |battle, enemy| battle.add_enemy(enemy));
+        battle.calc_initiative();
+        battle.run_enemy_turns(&mut self.rng);
+
+        self.battle_handler = Arc::new(Mutex::new(battle));
+        self.change_dungeon_state(DungeonState::Encounter);
+        self.update_battle_state();
+
+        Ok(())
+    }
+
+    /// Executes the attack option with the given index for the player during a battle.
+    pub fn execute_battle_action(&mut self, index: usize) -> Result<(), Error> {
+        let state = self.get_dungeon_state();
+        if state != DungeonState::Encounter {
+            return Err(Error::GameDataError(format!("Can not execute a battle action in the {:?} state.", state)));
+        }
+
+        let option = self.state().get_attack_options().get(index).cloned();
+
+        match option {
+            Some(AttackOption::Attack(description)) => {
+                self.battle_handler.lock().unwrap().player_attack(&mut self.rng, &description)?;
+            }
+            Some(AttackOption::None) | None => return Ok(()),
+        }
+        self.replay.commands.push(Command::BattleAction(index));
+
+        self.update_battle_state();
+        self.leave_won_battle()
+    }
+
+    /// Copies the battle progress into the game state and leaves the encounter once the battle is decided.
+    fn update_battle_state(&self) {
+        let battle = self.battle_handler.lock().unwrap();
+        let mut gs = self.state();
+
+        if let Some(player) = battle.get_player_values() {
+            gs.character.hp = player.hp;
+        }
+
+        gs.battle_events = battle.get_events().clone();
+        gs.battle_actors = battle.get_actor_values_by_order().iter()
+            .map(|actor| actor.lock().unwrap().clone())
+            .collect();
+
+        match battle.get_outcome() {
+            Some(BattleOutcome::Victory) => {
+                gs.dungeon_state = DungeonState::Result;
+            }
+            Some(BattleOutcome::Defeat) => {
+                gs.dungeon_state = DungeonState::GameOver;
+            }
+            None => {}
+        }
+    }
+
+    /// Moves on to the next room of the choice which started the fight once the player won it.
+    /// A fight won before the player acted is left by the room choice itself.
+    fn leave_won_battle(&mut self) -> Result<(), Error> {
+        let outcome = self.battle_handler.lock().unwrap().get_outcome();
+        if let Some(BattleOutcome::Victory) = outcome {
+            let next_room = self.next_room_after_battle.take();
+            self.change_room(next_room)?;
+        }
 
         Ok(())
     }
 
-    fn ability_check(&self, ability: Ability, dificulty: u8) -> Result<bool, Error> {
-        let character = self.game_state.lock().unwrap().character.clone();
-        let success = ability_check_with_nd6(character, ability, dificulty);
-        Ok(success)
+    fn ability_check(&mut self, ability: Ability, dificulty: u8) -> Result<CheckRoll, Error> {
+        let character = self.state().get_effective_character();
+        let roll = ability_check_with_nd6(&mut self.rng, character, ability, dificulty);
+        Ok(roll)
     }
 
-    fn change_room(&self) -> Result<(), Error>  {
+    fn change_room(&mut self, next_room: Option<u16>) -> Result<(), Error>  {
+        // a follow up room always wins over random and final rooms.
+        if let Some(room_id) = next_room {
+            return self.set_current_room(room_id);
+        }
+
         // check for levelPoints now and go to final room if needed.
-        let gs = self.game_state.lock().unwrap();
+        let gs = self.state();
         let level_points = gs.level_points;
+        let final_room_entered = gs.final_room_entered;
         let level = gs.get_current_level()?;
 
         drop(gs);
 
-        if level.rooms.len() == 0 || level_points >= level.level_points {
+        if final_room_entered {
+            self.complete_level()?;
+        } else if level.rooms.len() == 0 || level_points >= level.level_points {
             self.enter_final_room()?;
         } else {
             self.enter_random_room()?;
@@ -165,13 +515,13 @@ impl GameHandler {
         Ok(())
     }
 
-    pub fn enter_random_room(&self) -> Result<(), Error> {
-        let level = &self.game_state.lock().unwrap().get_current_level()?;
+    pub fn enter_random_room(&mut self) -> Result<(), Error> {
+        let level = &self.state().get_current_level()?;
         let amount = level.rooms.len();
         if amount == 0 {
             return Err(Error::GameDataError("Trying to enter random room, but no rooms exist.".to_string()));
         }
-        let random = thread_rng().gen_range(0..amount);
+        let random = self.rng.gen_range(0..amount);
         let room_id = level.rooms[random];
         self.set_current_room(room_id)?;
 
@@ -179,15 +529,16 @@ impl GameHandler {
     }
 
     pub fn change_dungeon_state(&self, state: DungeonState) {
-        self.game_state.lock().unwrap().dungeon_state = state;
+        self.state().dungeon_state = state;
     }
 
     fn enter_final_room(&self) -> Result<(), Error>  {
-        let opt_level = &self.game_state.lock().unwrap().current_level.clone();
+        let opt_level = &self.state().current_level.clone();
         match opt_level {
             Some(level) => {
                 let room_id = level.final_room;
                 self.set_current_room(room_id)?;
+                self.state().final_room_entered = true;
             }
             None => {
                 return Err(Error::GameDataError("No current level set.".to_string()));
@@ -197,49 +548,66 @@ impl GameHandler {
         Ok(())
     }
 
+    /// The current state of the game, locked until the guard is dropped.
+    pub fn state(&self) -> MutexGuard<'_, GameState> {
+        self.game_state.lock().unwrap()
+    }
+
+    /// A shared handle on the state for frontends which render it from another place than the game handler.
     pub fn get_game_state_clone(&self) -> Arc<Mutex<GameState>> {
         self.game_state.clone()
     }
 
     fn set_current_room(&self, room_id: u16) -> Result<(), Error> {
         let room = self.game_data.find_room_by_id(room_id)?;
-        self.game_state.lock().unwrap().set_current_room(room);
+        self.state().set_current_room(room);
         Ok(())
     }
 
     pub fn set_dungeon_state(&self, ds: DungeonState) {
-        self.game_state.lock().unwrap().dungeon_state = ds;
+        self.state().dungeon_state = ds;
+    }
+
+    /// Leaves the result or failure screen and goes back to the current room.
+    pub fn confirm_result(&mut self) {
+        self.replay.commands.push(Command::ConfirmResult);
+        self.set_dungeon_state(DungeonState::Room);
     }
 
     pub fn get_dungeon_state(&self) -> DungeonState {
-        self.game_state.lock().unwrap().dungeon_state.clone()
+        self.state().dungeon_state.clone()
     }
 
-    pub fn recive_damage(&self, dificulty: u8) -> u16 {
-        // TODO extract hardcoded ranges
-        let range = match dificulty {
-            1 | 2 => 0..=10,
-            3 => 8..=20,
-            4 => 15..=30,
-            _ => 20..=50,
-        };
+    /// Deals the damage of a failed ability check, rolled from the damage range of its difficulty.
+    /// A fumble always deals the maximum. Returns the damage and its type.
+    pub fn recive_damage(&mut self, dificulty: u8, fumble: bool) -> Result<(u16, Option<String>), Error> {
+        let range = self.game_data.find_damage_range(dificulty)?;
+        let damage_type = range.damage_type.clone();
 
-        let random_dmg = thread_rng().gen_range(range);
+        let random_dmg = if fumble {
+            DiceExpression::to_amount(range.damage.max())
+        } else {
+            range.damage.roll_amount(&mut self.rng)
+        };
 
-        self.game_state.lock().unwrap().remove_hp(random_dmg);
+        let mut gs = self.state();
+        gs.remove_hp(random_dmg);
+        if gs.character.hp == 0 {
+            gs.dungeon_state = DungeonState::GameOver;
+        }
 
-        random_dmg
+        Ok((random_dmg, damage_type))
     }
 
     pub fn gain_item(&mut self, id: &u16) -> Result<(), Error> {
         let item = self.game_data.find_item_by_id(*id)?;
-        self.game_state.lock().unwrap().owned_items.push(item.clone());
+        self.state().add_item(item);
         Ok(())
     }
 
     pub fn gain_skill_once(&mut self, id: &u16) -> Result<bool, Error> {
         let skill = self.game_data.find_skill_by_id(*id)?;
-        let mut gs = self.game_state.lock().unwrap();
+        let mut gs = self.state();
         if !gs.gained_skills.iter().any(|skill| &skill.id == id) {
             gs.gained_skills.push(skill.clone());
             return Ok(true);
@@ -249,20 +617,83 @@ impl GameHandler {
     }
 
     pub fn increase_level_points(&mut self, points: &u16) {
-        self.game_state.lock().unwrap().level_points += points;
+        self.state().level_points += points;
+    }
+
+    /// Returns the amount of character levels gained.
+    pub fn increase_xp(&mut self, points: &u16) -> u16 {
+        gain_xp(&mut self.state().character, *points)
+    }
+
+    /// Raises the ability by one, paid with a stat point from leveling up.
+    pub fn spend_stat_point(&mut self, ability: Ability) -> Result<(), Error> {
+        let mut gs = self.game_state.lock().unwrap();
+        if gs.character.stat_points == 0 {
+            return Err(Error::GameDataError("No stat points left to spend.".to_string()));
+        }
+
+        self.replay.commands.push(Command::SpendStatPoint(ability.clone()));
+
+        gs.character.stat_points -= 1;
+        *gs.character.get_ability_mut(&ability) += 1;
+        Ok(())
     }
 
-    pub fn increase_xp(&mut self, points: &u16) {
-        self.game_state.lock().unwrap().character.xp += points;
+    /// Whether the item at the index of `owned_items` can be used right now.
+    pub fn can_use_item(&self, index: usize) -> bool {
+        let gs = self.state();
+        let item = match gs.owned_items.get(index) {
+            Some(owned) => &owned.item,
+            None => return false,
+        };
+
+        match gs.dungeon_state {
+            DungeonState::Encounter => item.item_type == ItemType::Consumable,
+            DungeonState::GameOver | DungeonState::CampaignComplete => false,
+            _ => item.is_usable_outside_battle(),
+        }
+    }
+
+    /// Uses up one consumable of the stack at the index. During a battle this is the players turn.
+    pub fn use_item(&mut self, index: usize) -> Result<(), Error> {
+        if !self.can_use_item(index) {
+            return Err(Error::GameDataError(format!("Can not use item with index {}", index)));
+        }
+        self.replay.commands.push(Command::UseItem(index));
+
+        let gs = self.state();
+        let item = Rc::clone(&gs.owned_items[index].item);
+        let in_battle = matches!(gs.dungeon_state, DungeonState::Encounter);
+        drop(gs);
+
+        if in_battle {
+            self.battle_handler.lock().unwrap().player_use_item(&mut self.rng, &item)?;
+        } else {
+            for effect in &item.effects {
+                if let ItemEffect::Heal(dice) = effect {
+                    let hp = dice.roll_amount(&mut self.rng);
+                    self.state().heal(hp);
+                }
+            }
+        }
+
+        self.state().remove_item(index);
+        if in_battle {
+            self.update_battle_state();
+            self.leave_won_battle()?;
+        }
+
+        Ok(())
     }
 
-    pub fn equip_item_by_index(&self, index: usize) -> Result<(), Error> {
-        let id = match self.game_state.lock().unwrap().owned_items.get(index) {
-            Some(item) => item.id,
+    pub fn equip_item_by_index(&mut self, index: usize) -> Result<(), Error> {
+        let id = match self.state().owned_items.get(index) {
+            Some(owned) if owned.item.item_type != ItemType::Consumable => owned.item.id,
             _ => return Err(Error::GameDataError(format!("Can not equip item with index {}", index))),
         };
 
         self.equip_item(id)?;
+        self.replay.commands.push(Command::EquipItem(index));
 
         Ok(())
     }
@@ -271,7 +702,7 @@ impl GameHandler {
         if self.has_item(item_id) {
             // check type. To replace equipped item of that type. 
             let item = self.game_data.find_item_by_id(item_id)?;
-            let mut gs = self.game_state.lock().unwrap();
+            let mut gs = self.state();
             let old_item_index = gs.equipped_items.iter()
                 .position(|old_item| old_item.item_type == item.item_type);
 
@@ -289,12 +720,367 @@ impl GameHandler {
     }
 
     pub fn has_item(&self, item_id: u16) -> bool {
-        let first_index = self.game_state.lock().unwrap()
+        let first_index = self.state()
             .owned_items.iter()
-            .position(|item| item.id == item_id);
+            .position(|owned| owned.item.id == item_id);
         match first_index {
             Some(_index) => true,
             None => false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::DataPack;
+    use crate::models::models::{ Character, Level, Room, DamageRange };
+
+    fn build_game_data(levels: &str, rooms: &str) -> GameData {
+        let levels: Vec<Level> = serde_json::from_str(levels).unwrap();
+        let rooms: Vec<Room> = serde_json::from_str(rooms).unwrap();
+        let damage: Vec<DamageRange> = serde_json::from_str(
+            r#"[{ "dificulty": 1, "damage": "1d10" }, { "dificulty": 4, "damage": "3d6+12", "damage_type": "Falling" }]"#).unwrap();
+        GameData::new(levels, rooms, Vec::new(), Vec::new(), Vec::new(), Vec::new(), damage).unwrap()
+    }
+
+    fn current_room_id(handler: &GameHandler) -> u16 {
+        handler.state().current_room.as_ref().unwrap().id
+    }
+
+    #[test]
+    fn test_room_sequence() {
+        let levels = r#"[{ "name": "Test", "id": 1, "rooms": [4], "level_points": 100, "first_room": 1, "final_room": 9 }]"#;
+        let rooms = r#"[
+            { "id": 1, "title": "A", "text": "", "choices": [{ "text": "go", "consequences": [], "next": 2 }] },
+            { "id": 2, "title": "B", "text": "", "choices": [{ "text": "go", "consequences": [] }] },
+            { "id": 4, "title": "D", "text": "", "choices": [{ "text": "go", "consequences": [{ "t": "NextRoom", "c": 5 }] }] },
+            { "id": 5, "title": "E", "text": "", "choices": [{ "text": "go", "consequences": [] }] },
+            { "id": 9, "title": "X", "text": "", "choices": [] }
+        ]"#;
+
+        let mut handler = GameHandler::new(build_game_data(levels, rooms));
+        handler.start_game().unwrap();
+        assert_eq!(current_room_id(&handler), 1);
+
+        handler.execute_room_choice(0).unwrap();
+        assert_eq!(current_room_id(&handler), 2, "follows the next field of the choice");
+
+        handler.execute_room_choice(0).unwrap();
+        assert_eq!(current_room_id(&handler), 4, "random room without a follow up");
+
+        handler.execute_room_choice(0).unwrap();
+        assert_eq!(current_room_id(&handler), 5, "follows the NextRoom consequence");
+    }
+
+    #[test]
+    fn test_level_progression() {
+        let levels = r#"[
+            { "name": "One", "id": 1, "rooms": [], "level_points": 0, "first_room": 1, "final_room": 2, "next_level": 2 },
+            { "name": "Two", "id": 2, "rooms": [], "level_points": 0, "first_room": 3, "final_room": 4 }
+        ]"#;
+        let rooms = r#"[
+            { "id": 1, "title": "A", "text": "", "choices": [{ "text": "go", "consequences": [{ "t": "GainLevelPoints", "c": 5 }] }] },
+            { "id": 2, "title": "B", "text": "", "choices": [{ "text": "go", "consequences": [], "next": 5 }] },
+            { "id": 5, "title": "C", "text": "", "choices": [{ "text": "go", "consequences": [] }] },
+            { "id": 3, "title": "D", "text": "", "choices": [{ "text": "go", "consequences": [] }] },
+            { "id": 4, "title": "E", "text": "", "choices": [{ "text": "go", "consequences": [] }] }
+        ]"#;
+
+        let mut handler = GameHandler::new(build_game_data(levels, rooms));
+        handler.start_game().unwrap();
+
+        handler.execute_room_choice(0).unwrap();
+        handler.confirm_result();
+        assert_eq!(current_room_id(&handler), 2, "final room");
+
+        handler.execute_room_choice(0).unwrap();
+        assert_eq!(current_room_id(&handler), 5, "sequence of the final room");
+
+        handler.execute_room_choice(0).unwrap();
+        assert_eq!(current_room_id(&handler), 3, "first room of the next level");
+        assert_eq!(handler.state().level_points, 0);
+        assert_eq!(handler.state().current_level.as_ref().unwrap().id, 2);
+
+        handler.execute_room_choice(0).unwrap();
+        assert_eq!(current_room_id(&handler), 4);
+
+        handler.execute_room_choice(0).unwrap();
+        assert!(matches!(handler.get_dungeon_state(), DungeonState::CampaignComplete));
+    }
+
+    #[test]
+    fn test_game_over_on_failed_check() {
+        let levels = r#"[{ "name": "Test", "id": 1, "rooms": [2], "level_points": 100, "first_room": 1, "final_room": 2 }]"#;
+        let rooms = r#"[
+            { "id": 1, "title": "A", "text": "", "choices": [{ "text": "jump", "consequences": [{ "t": "AbilityCheck", "c": ["Agil", 5] }] }] },
+            { "id": 2, "title": "B", "text": "", "choices": [] }
+        ]"#;
+
+        let mut handler = GameHandler::new(build_game_data(levels, rooms));
+        handler.start_game().unwrap();
+
+        handler.state().character.agil = 0;
+        handler.state().character.hp = 1;
+
+        handler.execute_room_choice(0).unwrap();
+
+        assert_eq!(handler.state().character.hp, 0);
+        assert_eq!(handler.state().last_bad_results[0].name, "Falling", "difficulty 5 uses the range of 4");
+        assert!(matches!(handler.get_dungeon_state(), DungeonState::GameOver));
+        assert_eq!(current_room_id(&handler), 1, "no room change after death");
+
+        handler.reset_game().unwrap();
+        assert!(matches!(handler.get_dungeon_state(), DungeonState::Room));
+        let character = handler.state().character.clone();
+        assert_eq!(character.hp, character.hp_max);
+    }
+
+    #[test]
+    fn test_check_outcome_lists() {
+        let levels = r#"[{ "name": "Test", "id": 1, "rooms": [], "level_points": 100, "first_room": 1, "final_room": 1 }]"#;
+        let rooms = r#"[
+            { "id": 1, "title": "A", "text": "", "choices": [
+                { "text": "easy", "consequences": [{ "t": "AbilityCheck", "c": ["Agil", 0] }],
+                  "on_success": [{ "t": "GainXp", "c": 5 }], "on_failure": [{ "t": "GainXp", "c": 1 }], "next": 1 },
+                { "text": "close", "consequences": [{ "t": "AbilityCheck", "c": ["Agil", 1] }],
+                  "on_success": [{ "t": "GainXp", "c": 5 }], "on_failure": [{ "t": "GainXp", "c": 1 }], "next": 1 },
+                { "text": "hard", "consequences": [{ "t": "AbilityCheck", "c": ["Agil", 2] }, { "t": "GainXp", "c": 10 }],
+                  "on_success": [{ "t": "GainXp", "c": 5 }], "on_failure": [{ "t": "GainXp", "c": 1 }], "next": 1 }
+            ] }
+        ]"#;
+
+        let mut handler = GameHandler::new(build_game_data(levels, rooms));
+        handler.start_game().unwrap();
+
+        // without any dice the outcome only depends on the difficulty
+        handler.state().character.agil = 0;
+
+        let mut execute = |index: usize| {
+            handler.execute_room_choice(index).unwrap();
+            handler.confirm_result();
+            let gs = handler.state();
+            (gs.character.xp, gs.character.hp, gs.last_checks[0].roll.outcome)
+        };
+
+        let hp_max = Character::default().hp_max;
+        assert_eq!(execute(0), (5, hp_max, CheckOutcome::Success));
+        assert_eq!(execute(1), (6, hp_max, CheckOutcome::PartialSuccess), "partial falls back to on_failure without damage");
+
+        let (xp, hp, outcome) = execute(2);
+        assert_eq!(outcome, CheckOutcome::Failure);
+        assert_eq!(xp, 17, "a failure does not stop the remaining consequences");
+        assert!(hp < hp_max);
+    }
+
+    #[test]
+    fn test_level_up_and_stat_points() {
+        let levels = r#"[{ "name": "Test", "id": 1, "rooms": [], "level_points": 100, "first_room": 1, "final_room": 1 }]"#;
+        let rooms = r#"[{ "id": 1, "title": "A", "text": "", "choices": [{ "text": "learn", "consequences": [{ "t": "GainXp", "c": 60 }] }] }]"#;
+
+        let mut handler = GameHandler::new(build_game_data(levels, rooms));
+        handler.start_game().unwrap();
+        assert!(handler.spend_stat_point(Ability::Strg).is_err(), "no points before leveling up");
+
+        handler.execute_room_choice(0).unwrap();
+
+        let rewards: Vec<String> = handler.state().last_rewards.iter().map(|reward| reward.name.to_owned()).collect();
+        assert_eq!(rewards, vec!["Experience Points", "Level Up! You reached level 2"]);
+
+        let strg = handler.state().character.strg;
+        handler.spend_stat_point(Ability::Strg).unwrap();
+        let character = handler.state().character.clone();
+        assert_eq!((character.level, character.strg, character.stat_points), (2, strg + 1, 0));
+        assert_eq!(handler.get_replay().commands.last(), Some(&Command::SpendStatPoint(Ability::Strg)));
+    }
+
+    #[test]
+    fn test_same_seed_same_run() {
+        let levels = r#"[{ "name": "Test", "id": 1, "rooms": [1, 2, 3], "level_points": 100, "first_room": 1, "final_room": 3 }]"#;
+        let rooms = r#"[
+            { "id": 1, "title": "A", "text": "", "choices": [{ "text": "jump", "consequences": [{ "t": "AbilityCheck", "c": ["Agil", 2] }] }] },
+            { "id": 2, "title": "B", "text": "", "choices": [{ "text": "climb", "consequences": [{ "t": "AbilityCheck", "c": ["Strg", 4] }] }] },
+            { "id": 3, "title": "C", "text": "", "choices": [{ "text": "go", "consequences": [] }] }
+        ]"#;
+
+        let play = |seed: u64| -> Vec<(u16, u16)> {
+            let mut handler = GameHandler::with_seed(build_game_data(levels, rooms), seed);
+            handler.start_game().unwrap();
+            (0..10).map(|_| {
+                handler.execute_room_choice(0).unwrap();
+                handler.confirm_result();
+                let hp = handler.state().character.hp;
+                (current_room_id(&handler), hp)
+            }).collect()
+        };
+
+        assert_eq!(play(7), play(7));
+    }
+
+    #[test]
+    fn test_seed_in_state() {
+        let levels = r#"[{ "name": "Test", "id": 1, "rooms": [], "level_points": 0, "first_room": 1, "final_room": 1 }]"#;
+        let rooms = r#"[{ "id": 1, "title": "A", "text": "", "choices": [] }]"#;
+
+        let mut handler = GameHandler::with_seed(build_game_data(levels, rooms), 7);
+        assert_eq!(handler.state().seed, 7);
+
+        handler.reset_game().unwrap();
+        assert_eq!(handler.state().seed, handler.get_seed());
+    }
+
+    #[test]
+    fn test_character_class() {
+        let pack = DataPack {
+            levels: serde_json::from_str(r#"[{ "name": "Test", "id": 1, "rooms": [], "level_points": 0, "first_room": 1, "final_room": 1 }]"#).unwrap(),
+            rooms: serde_json::from_str(r#"[{ "id": 1, "title": "A", "text": "", "choices": [] }]"#).unwrap(),
+            items: serde_json::from_str(r#"[{ "id": 1, "name": "Sword", "item_type": "Weapon" }]"#).unwrap(),
+            skills: serde_json::from_str(r#"[{ "id": 1, "name": "Strong", "modifiers": [] }]"#).unwrap(),
+            classes: serde_json::from_str(r#"[{ "id": 1, "name": "Warrior", "hp_max": 120, "strg": 2, "agil": 1, "def": 2, "items": [1], "skills": [1],
+                "attacks": [{ "title": "Cleave", "attack_type": "physical", "dmg_min": 2, "dmg_max": 5 }] }]"#).unwrap(),
+            ..DataPack::default()
+        };
+
+        let mut character = Character::default();
+        character.class = Some(1);
+        let mut handler = GameHandler::new(GameData::from_pack(pack).unwrap());
+        handler.new_game(character).unwrap();
+
+        let state = handler.state();
+        assert_eq!(state.character_class.as_ref().unwrap().name, "Warrior");
+        assert_eq!(state.equipped_items.len(), 1);
+        assert_eq!(state.gained_skills.len(), 1);
+
+        let titles: Vec<String> = state.get_attack_options().iter().map(|option| match option {
+            AttackOption::Attack(description) => description.title.to_owned(),
+            AttackOption::None => "-".to_owned(),
+        }).collect();
+        assert_eq!(titles, vec!["Unarmed", "Cleave", "-", "-"]);
+    }
+
+    #[test]
+    fn test_use_items() {
+        let pack = DataPack {
+            levels: serde_json::from_str(r#"[{ "name": "Test", "id": 1, "rooms": [], "level_points": 0, "first_room": 1, "final_room": 1 }]"#).unwrap(),
+            rooms: serde_json::from_str(r#"[{ "id": 1, "title": "A", "text": "", "choices": [] }]"#).unwrap(),
+            items: serde_json::from_str(r#"[
+                { "id": 1, "name": "Potion", "item_type": "Consumable", "effects": [{ "t": "Heal", "c": "30" }] },
+                { "id": 2, "name": "Bomb", "item_type": "Consumable", "effects": [{ "t": "Damage", "c": "2d6" }] }
+            ]"#).unwrap(),
+            ..DataPack::default()
+        };
+
+        let mut handler = GameHandler::new(GameData::from_pack(pack).unwrap());
+        handler.start_game().unwrap();
+        handler.gain_item(&1).unwrap();
+        handler.gain_item(&1).unwrap();
+        handler.gain_item(&2).unwrap();
+
+        handler.state().character.hp = 50;
+        assert_eq!(handler.state().owned_items[0].quantity, 2, "potions stack");
+
+        assert!(!handler.can_use_item(1), "bombs only work in battle");
+        assert!(handler.use_item(1).is_err());
+        assert!(handler.equip_item_by_index(0).is_err(), "consumables can't be equipped");
+
+        handler.use_item(0).unwrap();
+        handler.use_item(0).unwrap();
+        let state = handler.state();
+        assert_eq!(state.character.hp, 100, "never above the maximum");
+        assert_eq!(state.owned_items.len(), 1, "the empty stack is removed");
+        assert_eq!(handler.get_replay().commands.last(), Some(&Command::UseItem(0)));
+    }
+
+    fn build_battle_pack(levels: &str, rooms: &str) -> DataPack {
+        DataPack {
+            levels: serde_json::from_str(levels).unwrap(),
+            rooms: serde_json::from_str(rooms).unwrap(),
+            encounters: serde_json::from_str(r#"[{ "id": 1, "text": "", "enemies": [[1, {"Amount": 1}]] }]"#).unwrap(),
+            enemies: serde_json::from_str(
+                r#"[{ "id": 1, "name": "Rat", "template": "basic_melee_fighter", "hp": 1, "strg": 0, "agil": 0, "def": 0 }]"#).unwrap(),
+            ..DataPack::default()
+        }
+    }
+
+    #[test]
+    fn test_fight_stops_consequences() {
+        let levels = r#"[{ "name": "Test", "id": 1, "rooms": [2], "level_points": 100, "first_room": 1, "final_room": 2 }]"#;
+        let rooms = r#"[
+            { "id": 1, "title": "A", "text": "", "choices": [{ "text": "fight", "consequences": [
+                { "t": "StartFight", "c": 1 }, { "t": "GainLevelPoints", "c": 5 }
+            ] }] },
+            { "id": 2, "title": "B", "text": "", "choices": [] }
+        ]"#;
+
+        let mut handler = GameHandler::new(GameData::from_pack(build_battle_pack(levels, rooms)).unwrap());
+        handler.start_game().unwrap();
+        handler.execute_room_choice(0).unwrap();
+
+        assert!(matches!(handler.get_dungeon_state(), DungeonState::Encounter));
+        assert_eq!(handler.state().level_points, 0, "no points after the fight started");
+        assert_eq!(current_room_id(&handler), 1, "no room change during the fight");
+    }
+
+    #[test]
+    fn test_fight_in_final_room() {
+        let levels = r#"[{ "name": "Test", "id": 1, "rooms": [2], "level_points": 5, "first_room": 1, "final_room": 3 }]"#;
+        let rooms = r#"[
+            { "id": 1, "title": "A", "text": "", "choices": [{ "text": "go", "consequences": [{ "t": "GainLevelPoints", "c": 5 }] }] },
+            { "id": 2, "title": "B", "text": "", "choices": [] },
+            { "id": 3, "title": "Boss", "text": "", "choices": [{ "text": "fight", "consequences": [{ "t": "StartFight", "c": 1 }] }] }
+        ]"#;
+
+        let mut handler = GameHandler::new(GameData::from_pack(build_battle_pack(levels, rooms)).unwrap());
+        handler.start_game().unwrap();
+        handler.execute_room_choice(0).unwrap();
+        handler.confirm_result();
+        assert_eq!(current_room_id(&handler), 3, "final room");
+
+        handler.execute_room_choice(0).unwrap();
+        assert!(matches!(handler.get_dungeon_state(), DungeonState::Encounter), "the boss fight is not skipped");
+        assert_eq!(current_room_id(&handler), 3);
+
+        handler.execute_battle_action(0).unwrap();
+        assert!(matches!(handler.get_dungeon_state(), DungeonState::CampaignComplete), "completed after the victory");
+    }
+
+    #[test]
+    fn test_fight_won_right_away() {
+        let levels = r#"[{ "name": "Test", "id": 1, "rooms": [3], "level_points": 100, "first_room": 1, "final_room": 3 }]"#;
+        let rooms = r#"[
+            { "id": 1, "title": "A", "text": "", "choices": [{ "text": "fight", "consequences": [{ "t": "StartFight", "c": 2 }], "next": 2 }] },
+            { "id": 2, "title": "B", "text": "", "choices": [] },
+            { "id": 3, "title": "C", "text": "", "choices": [] }
+        ]"#;
+        let mut pack = build_battle_pack(levels, rooms);
+        pack.encounters = serde_json::from_str(r#"[{ "id": 2, "text": "", "enemies": [[1, {"Amount": 0}]] }]"#).unwrap();
+
+        let mut handler = GameHandler::new(GameData::from_pack(pack).unwrap());
+        handler.start_game().unwrap();
+        handler.execute_room_choice(0).unwrap();
+
+        assert_eq!(current_room_id(&handler), 2, "follows the choice");
+        assert_eq!(handler.state().rooms_visited, 2, "entered only one room");
+    }
+
+    #[test]
+    fn test_actions_need_their_state() {
+        let levels = r#"[{ "name": "Test", "id": 1, "rooms": [2], "level_points": 100, "first_room": 1, "final_room": 2 }]"#;
+        let rooms = r#"[
+            { "id": 1, "title": "A", "text": "", "choices": [{ "text": "fight", "consequences": [{ "t": "StartFight", "c": 1 }], "next": 2 }] },
+            { "id": 2, "title": "B", "text": "", "choices": [{ "text": "go", "consequences": [] }] }
+        ]"#;
+
+        let mut handler = GameHandler::new(GameData::from_pack(build_battle_pack(levels, rooms)).unwrap());
+        handler.start_game().unwrap();
+        assert!(handler.execute_battle_action(0).is_err(), "no battle action in a room");
+
+        handler.execute_room_choice(0).unwrap();
+        assert_eq!(handler.get_dungeon_state(), DungeonState::Encounter);
+        assert!(handler.execute_room_choice(0).is_err(), "no room choice during a fight");
+        assert_eq!(current_room_id(&handler), 1, "the rejected choice changed nothing");
+
+        handler.execute_battle_action(0).unwrap();
+        assert_eq!(current_room_id(&handler), 2);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::errors::Error;
+use crate::Error;
 
 pub struct GlobalHandler<'a> {
     pub quit_fn: &'a mut dyn FnMut() -> Result<(), Error> ,
@@ -0,0 +1,72 @@
+//! The XP curve of the character.
+//!
+//! Reaching level `n` takes `XP_PER_LEVEL * (n - 1) * n / 2` experience points in total,
+//! so every level needs `XP_PER_LEVEL` points more than the one before.
+
+use crate::models::models::Character;
+
+pub const XP_PER_LEVEL: u32 = 50;
+pub const HP_PER_LEVEL: u16 = 10;
+pub const STAT_POINTS_PER_LEVEL: u16 = 1;
+
+/// The total experience points needed to reach the level.
+pub fn xp_for_level(level: u16) -> u32 {
+    let level = level.max(1) as u32;
+    XP_PER_LEVEL * (level - 1) * level / 2
+}
+
+pub fn level_for_xp(xp: u16) -> u16 {
+    let mut level = 1;
+    while xp_for_level(level + 1) <= xp.into() {
+        level += 1;
+    }
+    level
+}
+
+/// Adds the experience points and applies every level reached with them.
+/// Each level raises `hp_max`, heals by the same amount and grants stat points.
+/// Returns the amount of levels gained.
+pub fn gain_xp(character: &mut Character, xp: u16) -> u16 {
+    character.xp = character.xp.saturating_add(xp);
+
+    let level = level_for_xp(character.xp);
+    let gained = level.saturating_sub(character.level);
+
+    character.level += gained;
+    character.hp_max += gained * HP_PER_LEVEL;
+    character.hp = (character.hp + gained * HP_PER_LEVEL).min(character.hp_max);
+    character.stat_points += gained * STAT_POINTS_PER_LEVEL;
+
+    gained
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curve() {
+        assert_eq!(xp_for_level(1), 0);
+        assert_eq!(xp_for_level(2), 50);
+        assert_eq!(xp_for_level(3), 150);
+        assert_eq!(level_for_xp(0), 1);
+        assert_eq!(level_for_xp(149), 2);
+        assert_eq!(level_for_xp(150), 3);
+        assert_eq!(level_for_xp(u16::MAX), 51);
+    }
+
+    #[test]
+    fn test_gain_xp() {
+        let mut character = Character::default();
+        character.hp = 50;
+
+        assert_eq!(gain_xp(&mut character, 40), 0);
+        assert_eq!(gain_xp(&mut character, 120), 2);
+
+        assert_eq!(character.xp, 160);
+        assert_eq!(character.level, 3);
+        assert_eq!(character.hp_max, 120);
+        assert_eq!(character.hp, 70);
+        assert_eq!(character.stat_points, 2);
+    }
+}
@@ -0,0 +1,41 @@
+//! The Rusty Dungeon engine.
+//!
+//! Everything needed to run a game lives here and can be driven without a terminal.
+//! The `rusty-dungeon` binary is a TUI frontend on top of it.
+//!
+//! ```
+//! use rusty_dungeon::{ DataPack, GameData, GameHandler };
+//!
+//! let mut problems = Vec::new();
+//! let pack = DataPack::read_layered(&["./data"], &mut problems);
+//! assert!(problems.is_empty());
+//!
+//! let mut game_handler = GameHandler::new(GameData::from_pack(pack)?);
+//! game_handler.start_game()?;
+//! game_handler.execute_room_choice(0)?;
+//!
+//! assert!(game_handler.state().character.xp > 0);
+//! # Ok::<(), rusty_dungeon::Error>(())
+//! ```
+
+pub mod models;
+pub mod data;
+pub mod state;
+pub mod game_handler;
+pub mod battle_handler;
+pub mod entities;
+pub mod errors;
+pub mod random;
+pub mod dice;
+pub mod leveling;
+pub mod character_creation;
+pub mod damage;
+pub mod replay;
+pub mod simulation;
+
+pub use crate::models::{ Enemy, Encounter };
+pub use crate::data::{ DataPack, GameData };
+pub use crate::game_handler::GameHandler;
+pub use crate::battle_handler::BattleHandler;
+pub use crate::state::GameState;
+pub use crate::errors::Error;
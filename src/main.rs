@@ -1,14 +1,12 @@
-use crate::models::Enemy;
-use crate::models::Encounter;
 use std::sync::Mutex;
 use std::sync::Arc;
-use std::fs;
+use std::env;
 use std::io;
+use std::process;
 use std::sync::mpsc;
 use std::thread;
 use std::time::{Duration, Instant};
 use std::boxed::Box;
-use thiserror::Error;
 
 use crossterm::{
     event::{self, Event as CEvent, KeyCode, KeyModifiers},
@@ -24,39 +22,21 @@ use tui::{
     Terminal,
 };
 
-mod models;
-use crate::models::models::{ Level, Room, Item, Skill };
-
-mod data;
-use crate::data::GameData;
-
-mod game_handler;
-use crate::game_handler::GameHandler;
+use rusty_dungeon::{ character_creation, leveling, models, random, state };
+use rusty_dungeon::models::models::{ Character, Item, Skill };
+use rusty_dungeon::{ DataPack, GameData, GameHandler, GameState, Error };
+use rusty_dungeon::replay::Replay;
+use rusty_dungeon::simulation::{ find_policy, simulate };
 
 mod global_handler;
 use crate::global_handler::GlobalHandler;
 
-mod battle_handler;
-
-
-mod state;
-use crate::state::GameState;
-
 mod views;
 use crate::views::{ DungeonView, MenuView, CharacterView, ItemsView };
 
-mod errors;
-use crate::errors::Error;
-
-mod random;
-mod entities;
+mod cli;
+use crate::cli::{ Options, USAGE };
 
-const ITEMS_PATH: &str = "./data/items.json";
-const ROOMS_PATH: &str = "./data/rooms.json";
-const LEVEL_PATH: &str = "./data/levels.json";
-const SKILL_PATH: &str = "./data/skills.json";
-const ENCOUNTERS_PATH: &str = "./data/encounters.json";
-const ENEMIES_PATH: &str = "./data/enemies.json";
 
 enum Event<I> {
     Input(I),
@@ -84,23 +64,68 @@ impl From<MenuItem> for usize {
 
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
 
-    let levels: Vec<Level> = read_level_db().expect("can fetch level data");
-    let rooms: Vec<Room> = read_room_db().expect("can fetch rooms data");
-    let items: Vec<Item> = read_item_db().expect("can fetch items data");
-    let skills: Vec<Skill> = read_skill_db().expect("can fetch skills data");
-    let encounters: Vec<Encounter> = read_encounter_db().expect("can fetch encounter data");
-    let enemies: Vec<Enemy> = read_enemies_db().expect("can fetch enemies data");
+    let mut problems: Vec<String> = Vec::new();
+    let pack = DataPack::read_layered(&options.data_dirs, &mut problems);
 
-    let game_data = GameData::new(levels, rooms, items, skills, encounters, enemies);
+    if problems.is_empty() {
+        problems.extend(pack.validate().iter().map(|problem| problem.to_string()));
+    }
 
-    let game_handler = Arc::new(Mutex::new(GameHandler::new(game_data)));
+    if !problems.is_empty() {
+        problems.iter().for_each(|problem| eprintln!("{}", problem));
+        eprintln!("Found {} problem(s) in the game data.", problems.len());
+        process::exit(1);
+    }
 
+    if options.check_data {
+        println!("Game data is valid.");
+        return Ok(());
+    }
 
-    let mut main_game_handler = game_handler.lock().unwrap();
-    main_game_handler.start_game().expect("Can start game");
-    let state = main_game_handler.get_game_state_clone();
-    drop(main_game_handler);
+    let game_data = GameData::from_pack(pack)?;
+
+    if let Some(runs) = options.simulate {
+        let seed = options.seed.unwrap_or(0);
+        let report = find_policy(&options.policy)
+            .and_then(|policy| simulate(&game_data, policy.as_ref(), runs, seed, options.level));
+        match report {
+            Ok(report) => print!("{}", report),
+            Err(error) => {
+                eprintln!("Simulation failed: {}", error);
+                process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    let game_handler = match (&options.replay, options.seed) {
+        (Some(path), _) => {
+            let replayed = Replay::read_from_file(path).and_then(|replay| replay.play(game_data));
+            match replayed {
+                Ok(game_handler) => game_handler,
+                Err(error) => {
+                    eprintln!("Can not replay {}: {}", path, error);
+                    process::exit(1);
+                }
+            }
+        },
+        (None, seed) => {
+            match seed {
+                Some(seed) => GameHandler::with_seed(game_data, seed),
+                None => GameHandler::new(game_data),
+            }
+        },
+    };
+    let state = game_handler.get_game_state_clone();
+    let game_handler = Arc::new(Mutex::new(game_handler));
 
     enable_raw_mode().expect("can run in raw mode");
 
@@ -155,7 +180,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut items_view = ItemsView::new();
 
     let mut dungeon_view = DungeonView::new();
-    let menu_view = MenuView {};
+    // A replay has already started the game, otherwise the first run starts with a new character.
+    if options.replay.is_none() {
+        dungeon_view.start_character_creation(&Character::default(), game_handler.lock().unwrap().get_classes(), false);
+    }
+    let mut menu_view = MenuView::new();
     let character_view = CharacterView {};
 
     loop {
@@ -221,39 +250,53 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         match rx.recv()? {
             Event::Input(event) => {
+                let creating = dungeon_view.is_creating_character();
                 match event.code {
+                    KeyCode::Char('c') if event.modifiers.contains(KeyModifiers::CONTROL) => { // Break the ui loop
+                        global_handler.quit()?;
+                        break;
+                    },
+                    // Typing a name must not switch tabs.
+                    _ if creating => active_menu_item = MenuItem::Dungeon,
                     KeyCode::Char('d') => active_menu_item = MenuItem::Dungeon,
                     KeyCode::Char('i') => active_menu_item = MenuItem::Items,
                     KeyCode::Char('m') => active_menu_item = MenuItem::Menu,
-                    KeyCode::Char('c') => {
-                        if event.modifiers.contains(KeyModifiers::CONTROL) {
-                            break; // Break the ui loop
-                        } else {
-                            active_menu_item = MenuItem::Character
-                        }
-                     }
+                    KeyCode::Char('c') => active_menu_item = MenuItem::Character,
                     _ => {}
                 };
 
-                match active_menu_item {
+                let handled = match active_menu_item {
                     MenuItem::Dungeon => {
-                        dungeon_view.handle_input(event.code, &mut loop_game_handler.lock().unwrap())?;
+                        dungeon_view.handle_input(event.code, &mut loop_game_handler.lock().unwrap())
                     },
                     MenuItem:: Character => {
-                        character_view.handle_input(event.code, &mut loop_game_handler.lock().unwrap())?;
+                        character_view.handle_input(event.code, &mut loop_game_handler.lock().unwrap())
                     },
                     MenuItem::Menu => {
                         let res = menu_view.handle_input(event.code, &mut loop_game_handler.lock().unwrap(), &mut global_handler);
-                        if let Ok(should_continue) = res {
-                            if !should_continue {
-                                break;
-                            }
+                        // The menu quits on its own.
+                        if let Ok(false) = res {
+                            break;
                         }
+                        Ok(true)
                     },
                     MenuItem::Items => {
-                        items_view.handle_input(event.code, &mut loop_game_handler.lock().unwrap())?;
+                        items_view.handle_input(event.code, &mut loop_game_handler.lock().unwrap())
                     },
                 };
+
+                // Leave the raw mode before the program ends, also when it ends with an error.
+                match handled {
+                    Ok(true) => {},
+                    Ok(false) => {
+                        global_handler.quit()?;
+                        break;
+                    },
+                    Err(error) => {
+                        global_handler.quit()?;
+                        return Err(error.into());
+                    },
+                }
             },
             Event::Tick => {}
         }
@@ -262,42 +305,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     global_terminal.lock().unwrap().clear()?;
 
-    Ok(())
-}
-
-
-fn read_item_db() -> Result<Vec<Item>, Error> {
-    let db_content = fs::read_to_string(ITEMS_PATH)?;
-    let parsed: Vec<Item> = serde_json::from_str(&db_content)?;
-    Ok(parsed)
-}
-
-fn read_level_db() -> Result<Vec<Level>, Error> {
-    let db_content = fs::read_to_string(LEVEL_PATH)?;
-    let parsed: Vec<Level> = serde_json::from_str(&db_content)?;
-    Ok(parsed)
-}
-
-fn read_room_db() -> Result<Vec<Room>, Error> {
-    let db_content = fs::read_to_string(ROOMS_PATH)?;
-    let parsed: Vec<Room> = serde_json::from_str(&db_content)?;
-    Ok(parsed)
-}
-
-fn read_skill_db() -> Result<Vec<Skill>, Error> {
-    let db_content = fs::read_to_string(SKILL_PATH)?;
-    let parsed: Vec<Skill> = serde_json::from_str(&db_content)?;
-    Ok(parsed)
-}
-
-fn read_encounter_db() -> Result<Vec<Encounter>, Error> {
-    let db_content = fs::read_to_string(ENCOUNTERS_PATH)?;
-    let parsed: Vec<Encounter> = serde_json::from_str(&db_content)?;
-    Ok(parsed)
-}
+    if let Some(path) = &options.record {
+        game_handler.lock().unwrap().get_replay().write_to_file(path)?;
+    }
 
-fn read_enemies_db() -> Result<Vec<Enemy>, Error> {
-    let db_content = fs::read_to_string(ENEMIES_PATH)?;
-    let parsed: Vec<Enemy> = serde_json::from_str(&db_content)?;
-    Ok(parsed)
+    Ok(())
 }
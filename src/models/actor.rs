@@ -1,13 +1,17 @@
 use crate::models::attack_methods::AttackFunction;
 use crate::models::Enemy;
-use crate::models::models::Character;
+use crate::models::models::{ Ability, Character };
+use crate::models::{ StatusEffect, StatusKind };
 
 pub trait Actor {
     fn get_actor_values(&self) -> ActorValues;
-    fn selectAttack(&self) -> Box<AttackFunction>;
+    fn select_attack(&self) -> Box<AttackFunction>;
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Builds the behaviour of an enemy template for the given values.
+pub type ActorConstructor = fn(ActorValues) -> Box<dyn Actor>;
+
+#[derive(Debug, Clone)]
 pub struct ActorValues {
     pub name: String,
     pub hp: u16,
@@ -17,11 +21,69 @@ pub struct ActorValues {
     pub def: u16,
 
     pub is_player: bool,
+    pub actor: Option<ActorConstructor>,
 
     pub initiative: Option<u16>,
+
+    pub status_effects: Vec<StatusEffect>,
 }
 
 impl ActorValues {
+    pub fn get_ability_mut(&mut self, ability: &Ability) -> &mut u16 {
+        match ability {
+            Ability::Strg => &mut self.strg,
+            Ability::Agil => &mut self.agil,
+            Ability::Def => &mut self.def,
+        }
+    }
+
+    pub fn heal(&mut self, hp: u16) {
+        self.hp = self.hp.saturating_add(hp).min(self.hp_max);
+    }
+
+    /// The ability with the modifiers of the active status effects applied.
+    pub fn get_effective_ability(&self, ability: &Ability) -> u16 {
+        let value = match ability {
+            Ability::Strg => self.strg,
+            Ability::Agil => self.agil,
+            Ability::Def => self.def,
+        };
+
+        self.status_effects.iter().fold(value, |value, effect| match (&effect.kind, ability) {
+            (StatusKind::DefenceUp, Ability::Def) => value.saturating_add(effect.amount),
+            (StatusKind::Weakened, Ability::Strg) => value.saturating_sub(effect.amount),
+            _ => value,
+        })
+    }
+
+    /// An effect of a kind the actor already has doesn't stack, the longer and stronger values are kept.
+    pub fn add_status(&mut self, effect: StatusEffect) {
+        match self.status_effects.iter_mut().find(|active| active.kind == effect.kind) {
+            Some(active) => {
+                active.turns = active.turns.max(effect.turns);
+                active.amount = active.amount.max(effect.amount);
+            }
+            None => self.status_effects.push(effect),
+        }
+    }
+
+    pub fn get_status(&self, kind: &StatusKind) -> Option<&StatusEffect> {
+        self.status_effects.iter().find(|effect| &effect.kind == kind)
+    }
+
+    /// Ends every negative status effect.
+    pub fn cure(&mut self) {
+        self.status_effects.retain(|effect| !effect.kind.is_negative());
+    }
+
+    /// Counts down the turns of every effect and returns the effects which ended.
+    pub fn count_down_statuses(&mut self) -> Vec<StatusEffect> {
+        self.status_effects.iter_mut().for_each(|effect| effect.turns = effect.turns.saturating_sub(1));
+        let (ended, active) = self.status_effects.drain(..).partition(|effect| effect.turns == 0);
+        self.status_effects = active;
+        ended
+    }
+
     pub fn apply_damage(&mut self, dmg: u16) {
         if self.hp < dmg {
             self.hp = 0;
@@ -41,7 +103,9 @@ impl From<&Character> for ActorValues {
             agil: item.agil,
             def: item.def,
             is_player: true,
+            actor: None,
             initiative: None,
+            status_effects: Vec::new(),
         }
     }
 }
@@ -56,7 +120,46 @@ impl From<&Enemy> for ActorValues {
             agil: item.agil,
             def: item.def,
             is_player: false,
+            actor: item.actor,
             initiative: None,
+            status_effects: Vec::new(),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_status(kind: StatusKind, turns: u16, amount: u16) -> StatusEffect {
+        StatusEffect { kind, turns, amount }
+    }
+
+    #[test]
+    fn test_effective_ability() {
+        let mut values = ActorValues::from(&Character::default());
+        values.add_status(build_status(StatusKind::DefenceUp, 2, 3));
+        values.add_status(build_status(StatusKind::Weakened, 2, 5));
+
+        assert_eq!(values.get_effective_ability(&Ability::Def), 5);
+        assert_eq!(values.get_effective_ability(&Ability::Strg), 0, "never below zero");
+        assert_eq!(values.get_effective_ability(&Ability::Agil), 2);
+    }
+
+    #[test]
+    fn test_status_effects_refresh_and_end() {
+        let mut values = ActorValues::from(&Character::default());
+        values.add_status(build_status(StatusKind::Poison, 3, 1));
+        values.add_status(build_status(StatusKind::Poison, 1, 2));
+        values.add_status(build_status(StatusKind::Stun, 1, 0));
+        assert_eq!(values.status_effects, vec![build_status(StatusKind::Poison, 3, 2), build_status(StatusKind::Stun, 1, 0)]);
+
+        let ended = values.count_down_statuses();
+        assert_eq!(ended, vec![build_status(StatusKind::Stun, 0, 0)]);
+        assert_eq!(values.status_effects, vec![build_status(StatusKind::Poison, 2, 2)]);
+
+        values.add_status(build_status(StatusKind::DefenceUp, 2, 1));
+        values.cure();
+        assert_eq!(values.status_effects, vec![build_status(StatusKind::DefenceUp, 2, 1)], "only negative effects are cured");
+    }
+}
@@ -1,5 +1,6 @@
 
 use crate::models::BattleEvent;
 use crate::models::ActorValues;
+use crate::random::GameRng;
 
-pub type AttackFunction = dyn Fn(&mut ActorValues, &mut ActorValues) -> BattleEvent;
\ No newline at end of file
+pub type AttackFunction = dyn Fn(&mut GameRng, &mut ActorValues, &mut ActorValues) -> BattleEvent;
\ No newline at end of file
@@ -1,10 +1,20 @@
-#[derive(Clone)]
+use serde::{Deserialize, Serialize};
+use crate::models::StatusEffect;
+
+/// The number of attack tiles in a battle.
+pub const ATTACK_SLOTS: usize = 4;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct AttackDescription {
     pub title: String,
     pub attack_type: String,
     pub dmg_min: usize,
     pub dmg_max: usize,
+    #[serde(default)]
     pub special_effect: String,
+    /// Put on the target when the attack deals damage.
+    #[serde(default)]
+    pub status_effects: Vec<StatusEffect>,
 }
 
 #[derive(Clone)]
@@ -13,4 +23,4 @@ pub enum AttackOption {
     None
 }
 
-pub type AttackOptions = Vec<AttackOption>;
\ No newline at end of file
+pub type AttackOptions = Vec<AttackOption>;
@@ -1,11 +1,11 @@
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct BattleEvents {
     pub events: Vec<BattleEvent>
 }
 
 impl BattleEvents {
     pub fn new() -> BattleEvents {
-        BattleEvents { events: Vec::new() }
+        BattleEvents::default()
     }
 }
 
@@ -16,14 +16,3 @@ pub struct BattleEvent {
     pub target: String,
     pub effect: String,
 }
-
-impl BattleEvent {
-    pub fn default() -> BattleEvent {
-        BattleEvent {
-            title: "Event".to_owned(),
-            source: "Default".to_owned(),
-            target: "Default".to_owned(),
-            effect: "No Effect".to_owned(),
-        }
-    }
-}
\ No newline at end of file
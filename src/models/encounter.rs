@@ -1,5 +1,7 @@
 use crate::data::WithId;
 use serde::Deserialize;
+use rand::prelude::*;
+use crate::random::GameRng;
 
 #[derive(Clone, Deserialize)]
 pub struct Encounter {
@@ -15,10 +17,45 @@ impl WithId for Encounter {
 }
 
 #[derive(Clone, Deserialize)]
-pub struct EnemyDeclaration(u16, EnemyAmount);
+pub struct EnemyDeclaration(pub u16, pub EnemyAmount);
 
 #[derive(Clone, Deserialize)]
 pub enum EnemyAmount {
     Amount(u8),
     Range(u8, u8),
-}
\ No newline at end of file
+}
+
+impl EnemyAmount {
+    /// Resolves the declaration into a concrete number of enemies.
+    pub fn roll(&self, rng: &mut GameRng) -> u8 {
+        match self {
+            EnemyAmount::Amount(amount) => *amount,
+            EnemyAmount::Range(min, max) => rng.gen_range(*min..=*max.max(min)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::new_rng;
+
+    #[test]
+    fn test_roll_amount() {
+        assert_eq!(EnemyAmount::Amount(3).roll(&mut new_rng(1)), 3);
+    }
+
+    #[test]
+    fn test_roll_range() {
+        let mut rng = new_rng(1);
+        for _ in 0..20 {
+            let amount = EnemyAmount::Range(1, 3).roll(&mut rng);
+            assert!((1..=3).contains(&amount));
+        }
+    }
+
+    #[test]
+    fn test_roll_inverted_range() {
+        assert_eq!(EnemyAmount::Range(2, 1).roll(&mut new_rng(1)), 2);
+    }
+}
@@ -1,4 +1,6 @@
 use crate::data::WithId;
+use crate::entities::find_template;
+use crate::models::ActorConstructor;
 use serde::Deserialize;
 
 
@@ -14,23 +16,72 @@ pub struct Enemy {
     pub strg: u16,
     pub agil: u16,
     pub def: u16,
+    #[serde(skip)]
+    pub actor: Option<ActorConstructor>,
 }
 
 impl Enemy {
     pub fn new(id: u16, name: &str) -> Enemy {
+        let template = "basic_melee_fighter";
         Enemy { 
             id,
             name: name.to_owned(),
-            template: "basic_melee_fighter".to_owned(),
+            template: template.to_owned(),
             hp: 0,
             hp_max: 0,
             strg: 0,
             agil: 0,
             def: 0,
+            actor: find_template(template).ok(),
+        }
+    }
+
+    /// Creates a fresh instance of this enemy at full health.
+    /// A number is appended to the name to tell apart several enemies of the same kind.
+    pub fn spawn(&self, number: Option<u8>) -> Enemy {
+        let mut enemy = self.clone();
+        enemy.hp = enemy.hp_max;
+        if let Some(number) = number {
+            enemy.name = format!("{} {}", self.name, number);
         }
+        enemy
     }
 }
 
 impl WithId for Enemy {
     fn get_id(&self) -> u16 { self.id }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_template() -> Enemy {
+        serde_json::from_str(
+            r#"{ "id": 3, "name": "Cultist", "template": "basic_melee_fighter", "hp": 12, "strg": 2, "agil": 3, "def": 1 }"#).unwrap()
+    }
+
+    #[test]
+    fn test_spawn() {
+        let template = build_template();
+        assert_eq!(template.hp, 0, "the template has no current hp");
+
+        let enemy = template.spawn(None);
+        assert_eq!(enemy.name, "Cultist");
+        assert_eq!((enemy.hp, enemy.hp_max), (12, 12));
+        assert_eq!((enemy.strg, enemy.agil, enemy.def), (2, 3, 1));
+        assert_eq!(enemy.template, "basic_melee_fighter");
+    }
+
+    #[test]
+    fn test_spawn_numbers_duplicates() {
+        let template = build_template();
+        let first = template.spawn(Some(1));
+        let second = template.spawn(Some(2));
+
+        assert_eq!(first.name, "Cultist 1");
+        assert_eq!(second.name, "Cultist 2");
+        assert_eq!((second.id, second.hp, second.hp_max), (3, 12, 12));
+        assert_eq!(template.name, "Cultist", "the template keeps its name");
+    }
+}
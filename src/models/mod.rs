@@ -6,8 +6,10 @@ pub mod attack_methods;
 pub mod models;
 pub mod attack_options;
 pub mod battle_events;
+pub mod status_effect;
 
 pub use battle_events::*;
+pub use status_effect::*;
 pub use enemy::Enemy;
 pub use actor::*;
 pub use encounter::*;
\ No newline at end of file
@@ -1,8 +1,13 @@
+use std::fmt;
 use crate::data::WithId;
+use crate::dice::DiceExpression;
+use crate::models::attack_options::AttackDescription;
+use crate::models::StatusEffect;
+use crate::random::{ CheckOutcome, CheckRoll };
 use serde::{Deserialize, Serialize};
 use strum_macros::Display;
 
-#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum Ability {
     Strg,
     Agil,
@@ -18,12 +23,73 @@ pub enum RoomResult {
     GainSkill(u16),
     StartFight(u16),
     AbilityCheck(Ability, u8),
+    NextRoom(u16),
+}
+
+/// The damage a failed ability check of the given difficulty deals.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DamageRange {
+    pub dificulty: u8,
+    pub damage: DiceExpression,
+    #[serde(default)]
+    pub damage_type: Option<String>,
+}
+
+impl DamageRange {
+    /// Finds the range of the difficulty. Difficulties without an own entry use the closest lower one,
+    /// or the lowest entry if there is none below.
+    pub fn find(ranges: &[DamageRange], dificulty: u8) -> Option<&DamageRange> {
+        ranges.iter()
+            .filter(|range| range.dificulty <= dificulty)
+            .max_by_key(|range| range.dificulty)
+            .or_else(|| ranges.iter().min_by_key(|range| range.dificulty))
+    }
+}
+
+impl WithId for DamageRange {
+    fn get_id(&self) -> u16 {
+        self.dificulty.into()
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Choice {
     pub text: String,
-    pub consequences: Vec<RoomResult>
+    pub consequences: Vec<RoomResult>,
+    #[serde(default)]
+    pub next: Option<u16>,
+    /// Executed after the consequences when every ability check of the choice succeeded.
+    #[serde(default)]
+    pub on_success: Vec<RoomResult>,
+    /// Executed when the worst check was a partial success. Falls back to `on_failure` if not set.
+    #[serde(default)]
+    pub on_partial: Option<Vec<RoomResult>>,
+    /// Executed when an ability check of the choice failed.
+    #[serde(default)]
+    pub on_failure: Vec<RoomResult>,
+}
+
+impl Choice {
+    /// Choices with outcome lists don't stop at a failed ability check.
+    pub fn has_outcomes(&self) -> bool {
+        !self.on_success.is_empty() || self.on_partial.is_some() || !self.on_failure.is_empty()
+    }
+
+    pub fn consequences_for(&self, outcome: CheckOutcome) -> &[RoomResult] {
+        match outcome {
+            CheckOutcome::CriticalSuccess | CheckOutcome::Success => &self.on_success,
+            CheckOutcome::PartialSuccess => self.on_partial.as_ref().unwrap_or(&self.on_failure),
+            CheckOutcome::Failure | CheckOutcome::Fumble => &self.on_failure,
+        }
+    }
+
+    /// Every consequence the choice may execute.
+    pub fn all_consequences(&self) -> impl Iterator<Item = &RoomResult> {
+        self.consequences.iter()
+            .chain(self.on_success.iter())
+            .chain(self.on_partial.iter().flatten())
+            .chain(self.on_failure.iter())
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -35,6 +101,12 @@ pub struct Room {
 }
 
 
+impl WithId for Room {
+    fn get_id(&self) -> u16 {
+        self.id
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Level {
     pub name: String,
@@ -43,26 +115,82 @@ pub struct Level {
     pub level_points: u16,
     pub first_room: u16,
     pub final_room: u16,
+    #[serde(default)]
+    pub next_level: Option<u16>,
+}
+
+impl WithId for Level {
+    fn get_id(&self) -> u16 {
+        self.id
+    }
 }
 
 #[derive(Serialize, Deserialize, Display, Clone, PartialEq)]
 pub enum ItemType {
     Weapon,
     Armor,
+    Consumable,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// What using a consumable item does.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "t", content = "c")]
+pub enum ItemEffect {
+    /// Restores health, never above the maximum.
+    Heal(DiceExpression),
+    /// Raises an ability until the end of the battle.
+    Buff(Ability, u16),
+    /// Deals damage to the first enemy still standing.
+    Damage(DiceExpression),
+    /// Ends negative status effects.
+    Cure,
+    /// Negative effects are put on the first enemy still standing, the others on the player.
+    Status(StatusEffect),
+}
+
+impl fmt::Display for ItemEffect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ItemEffect::Heal(dice) => write!(f, "Heal {}", dice),
+            ItemEffect::Buff(ability, amount) => write!(f, "+{} {:?}", amount, ability),
+            ItemEffect::Damage(dice) => write!(f, "Damage {}", dice),
+            ItemEffect::Cure => write!(f, "Cure"),
+            ItemEffect::Status(status) => write!(f, "{}", status),
+        }
+    }
+}
+
+impl ItemEffect {
+    /// Buffs, damage and status effects only exist during a battle.
+    pub fn is_battle_only(&self) -> bool {
+        !matches!(self, ItemEffect::Heal(_))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Character {
     pub id: u16,
     pub name: String,
     pub hp: u16,
     pub hp_max: u16,
     pub xp: u16,
+    #[serde(default = "default_level")]
+    pub level: u16,
+    /// Points gained by leveling up, not yet spent on an ability.
+    #[serde(default)]
+    pub stat_points: u16,
+    /// The id of the character class, characters of game data without classes have none.
+    #[serde(default)]
+    pub class: Option<u16>,
     pub strg: u16,
     pub agil: u16,
     pub def: u16,
 }
 
+fn default_level() -> u16 {
+    1
+}
+
 impl Character {
     pub fn default() -> Character {
         Character {
@@ -71,11 +199,68 @@ impl Character {
             hp: 100,
             hp_max: 100,
             xp: 0,
+            level: 1,
+            stat_points: 0,
+            class: None,
             strg: 2,
             agil: 2,
             def: 2,
         }
     }
+
+    pub fn get_ability(&self, ability: &Ability) -> u16 {
+        match ability {
+            Ability::Strg => self.strg,
+            Ability::Agil => self.agil,
+            Ability::Def => self.def,
+        }
+    }
+
+    pub fn get_ability_mut(&mut self, ability: &Ability) -> &mut u16 {
+        match ability {
+            Ability::Strg => &mut self.strg,
+            Ability::Agil => &mut self.agil,
+            Ability::Def => &mut self.def,
+        }
+    }
+}
+
+/// An archetype the player picks for a new character.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CharacterClass {
+    pub id: u16,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub hp_max: u16,
+    /// The lowest value of each ability a character of the class can start with.
+    pub strg: u16,
+    pub agil: u16,
+    pub def: u16,
+    /// Ids of the items the character starts with, they are equipped right away.
+    #[serde(default)]
+    pub items: Vec<u16>,
+    #[serde(default)]
+    pub skills: Vec<u16>,
+    /// Attacks available in every battle in addition to the unarmed attack.
+    #[serde(default)]
+    pub attacks: Vec<AttackDescription>,
+}
+
+impl CharacterClass {
+    pub fn get_ability(&self, ability: &Ability) -> u16 {
+        match ability {
+            Ability::Strg => self.strg,
+            Ability::Agil => self.agil,
+            Ability::Def => self.def,
+        }
+    }
+}
+
+impl WithId for CharacterClass {
+    fn get_id(&self) -> u16 {
+        self.id
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -92,6 +277,27 @@ pub struct Item {
     
     #[serde(default = "default_ability")]
     pub def: u16,
+
+    /// Attacks of a weapon, available while it is equipped.
+    #[serde(default)]
+    pub attacks: Vec<AttackDescription>,
+
+    /// Effects of a consumable, the item is used up when they are applied.
+    #[serde(default)]
+    pub effects: Vec<ItemEffect>,
+}
+
+impl Item {
+    /// Consumables with a battle only effect can't be used outside of a battle.
+    pub fn is_usable_outside_battle(&self) -> bool {
+        self.item_type == ItemType::Consumable && !self.effects.iter().any(ItemEffect::is_battle_only)
+    }
+}
+
+impl WithId for Item {
+    fn get_id(&self) -> u16 {
+        self.id
+    }
 }
 
 fn default_ability() -> u16 {
@@ -114,6 +320,8 @@ pub struct Skill {
     pub id: u16,
     pub name: String,
     pub modifiers: Vec<SkillModifier>,
+    #[serde(default)]
+    pub attacks: Vec<AttackDescription>,
 }
 
 impl WithId for Skill {
@@ -127,6 +335,8 @@ pub enum RewardType {
     Item(ItemType),
     Skill,
     Xp,
+    LevelPoints,
+    LevelUp,
 }
 
 #[derive(Clone)]
@@ -147,4 +357,13 @@ pub struct BadResult {
     pub bad_result_type: BadResultType,
     pub name: String,
     pub amount: usize,
+}
+
+/// An ability check made while executing a room choice and the damage a failed check caused.
+#[derive(Clone)]
+pub struct CheckResult {
+    pub ability: Ability,
+    pub dificulty: u8,
+    pub roll: CheckRoll,
+    pub damage: u16,
 }
\ No newline at end of file
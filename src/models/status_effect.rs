@@ -0,0 +1,49 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use strum_macros::Display;
+
+#[derive(Serialize, Deserialize, Display, Clone, Debug, PartialEq)]
+pub enum StatusKind {
+    /// Loses `amount` hp at the start of every turn.
+    Poison,
+    /// Skips the turn.
+    Stun,
+    /// Loses `amount` hp at the end of every turn.
+    Bleed,
+    /// Blocks with `amount` more def.
+    #[strum(serialize = "Defence Up")]
+    DefenceUp,
+    /// Hits with `amount` less strg.
+    Weakened,
+}
+
+impl StatusKind {
+    /// Negative effects are put on enemies and ended by a cure.
+    pub fn is_negative(&self) -> bool {
+        !matches!(self, StatusKind::DefenceUp)
+    }
+
+    /// Stun has no strength, every other effect does nothing without an amount.
+    pub fn needs_amount(&self) -> bool {
+        !matches!(self, StatusKind::Stun)
+    }
+}
+
+/// A timed effect on a battle actor, it ends after the actor finished `turns` turns.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub turns: u16,
+    #[serde(default)]
+    pub amount: u16,
+}
+
+impl fmt::Display for StatusEffect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.kind.needs_amount() {
+            write!(f, "{} {} ({} turns)", self.kind, self.amount, self.turns)
+        } else {
+            write!(f, "{} ({} turns)", self.kind, self.turns)
+        }
+    }
+}
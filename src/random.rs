@@ -1,52 +1,90 @@
 use crate::models::models::Character;
 use crate::models::models::Ability;
+use crate::dice::DiceExpression;
 use rand::prelude::*;
+use rand::rngs::StdRng;
 
-const DEFAULT_N6_TRESHOLD: u8 = 4;
+pub const DEFAULT_N6_TRESHOLD: u8 = 4;
 
-pub fn ability_check_with_nd6 (character: Character, ability: Ability, dificulty: u8) -> bool {
-    let check = |score: u16, dif: u8| -> bool {
-        let res = roll_nd6_against(score, DEFAULT_N6_TRESHOLD);
-        let success = res.1 >= dif.into();
-        success
+/// Successes above the difficulty needed for a critical success.
+pub const CRITICAL_MARGIN: i16 = 2;
+
+/// The random number generator used for everything random in a game.
+pub type GameRng = StdRng;
+
+pub fn new_rng(seed: u64) -> GameRng {
+    StdRng::seed_from_u64(seed)
+}
+
+/// Ordered from the worst to the best outcome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckOutcome {
+    Fumble,
+    Failure,
+    PartialSuccess,
+    Success,
+    CriticalSuccess,
+}
+
+impl CheckOutcome {
+    pub fn is_success(&self) -> bool {
+        *self >= CheckOutcome::Success
+    }
+}
+
+/// The result of an ability check rolled as a pool of d6.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckRoll {
+    pub successes: u16,
+    /// Successes above (or below) the difficulty.
+    pub margin: i16,
+    pub outcome: CheckOutcome,
+}
+
+impl CheckRoll {
+    /// Reaching the difficulty is a success, `CRITICAL_MARGIN` more successes a critical one.
+    /// Missing it by one success is a partial success. No successes with more than half of the dice showing 1
+    /// is a fumble.
+    pub fn from_dice(dice: &[u16], treshold: u8, dificulty: u8) -> CheckRoll {
+        let successes = dice.iter().filter(|die| **die >= treshold.into()).count() as u16;
+        let ones = dice.iter().filter(|die| **die == 1).count();
+        let margin = successes as i16 - dificulty as i16;
+
+        let outcome = match margin {
+            m if m >= CRITICAL_MARGIN => CheckOutcome::CriticalSuccess,
+            m if m >= 0 => CheckOutcome::Success,
+            _ if successes == 0 && ones * 2 > dice.len() => CheckOutcome::Fumble,
+            -1 => CheckOutcome::PartialSuccess,
+            _ => CheckOutcome::Failure,
+        };
+
+        CheckRoll { successes, margin, outcome }
+    }
+}
+
+/// Rolls one d6 per point of the ability, every die reaching `DEFAULT_N6_TRESHOLD` is a success.
+pub fn ability_check_with_nd6 (rng: &mut GameRng, character: Character, ability: Ability, dificulty: u8) -> CheckRoll {
+    let check = |score: u16, dif: u8| -> CheckRoll {
+        let roll = DiceExpression::d6_pool(score, DEFAULT_N6_TRESHOLD).roll(rng);
+        CheckRoll::from_dice(&roll.rolls, DEFAULT_N6_TRESHOLD, dif)
     };
     ability_check(character, ability, dificulty, check)
 }
 
-fn ability_check<R>(character: Character, ability: Ability, dificulty: u8, check_fn: R ) -> bool 
+fn ability_check<R, T>(character: Character, ability: Ability, dificulty: u8, mut check_fn: R ) -> T
 where 
-    R: Fn(u16, u8) -> bool,
+    R: FnMut(u16, u8) -> T,
 {
-    let score = match ability {
-        Ability::Strg => character.strg,
-        Ability::Agil => character.agil,
-        Ability::Def => character.def,
-    };
+    let score = character.get_ability(&ability);
 
     check_fn(score, dificulty)
 }
 
 
-pub fn roll_nd6_against(rolls: u16, treshold: u8) -> (u16, u16) {
-    let mut rng = thread_rng();
-    let mut throws = 0;
-    let mut successes: u16 = 0;
-    let mut fails: u16 = 0;
-    
-    while throws < rolls {
-        let res = rng.gen_range(1..6);
-        match res {
-            r if r >= treshold => {
-                successes += 1;
-            }
-            _ => {
-                fails += 1;
-            }
-        }
-        throws += 1;
-    }
-
-    (successes, fails)
+/// Rolls a pool of d6 and returns the amount of successes and fails.
+pub fn roll_nd6_against(rng: &mut GameRng, rolls: u16, treshold: u8) -> (u16, u16) {
+    let successes = DiceExpression::d6_pool(rolls, treshold).roll(rng).total as u16;
+    (successes, rolls - successes)
 }
 
 #[cfg(test)]
@@ -57,7 +95,7 @@ mod tests {
     #[test]
     fn test_roll() {
         let rolls = 5;
-        let res = roll_nd6_against(rolls, 4);
+        let res = roll_nd6_against(&mut new_rng(1), rolls, 4);
         let results = res.0 + res.1;
         assert_eq!(results, rolls);
     }
@@ -65,7 +103,7 @@ mod tests {
     #[test]
     fn test_guaranteed_fails() {
         let rolls = 5;
-        let res = roll_nd6_against(rolls, 7);
+        let res = roll_nd6_against(&mut new_rng(1), rolls, 7);
         assert_eq!(res.0, 0);
         assert_eq!(res.1, rolls);
     }
@@ -73,11 +111,39 @@ mod tests {
     #[test]
     fn test_guaranteed_successes() {
         let rolls = 3;
-        let res = roll_nd6_against(rolls, 1);
+        let res = roll_nd6_against(&mut new_rng(1), rolls, 1);
         assert_eq!(res.0, rolls);
         assert_eq!(res.1, 0);
     }
 
+    #[test]
+    fn test_same_seed_same_rolls() {
+        let first: Vec<(u16, u16)> = (0..10).map({
+            let mut rng = new_rng(42);
+            move |_| roll_nd6_against(&mut rng, 5, 4)
+        }).collect();
+        let second: Vec<(u16, u16)> = (0..10).map({
+            let mut rng = new_rng(42);
+            move |_| roll_nd6_against(&mut rng, 5, 4)
+        }).collect();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_check_outcomes() {
+        let outcome = |dice: &[u16], dificulty: u8| CheckRoll::from_dice(dice, 4, dificulty).outcome;
+
+        assert_eq!(outcome(&[4, 5, 6, 6], 2), CheckOutcome::CriticalSuccess);
+        assert_eq!(outcome(&[4, 5, 2], 2), CheckOutcome::Success);
+        assert_eq!(outcome(&[4, 2, 2], 2), CheckOutcome::PartialSuccess);
+        assert_eq!(outcome(&[2, 2, 3], 2), CheckOutcome::Failure);
+        assert_eq!(outcome(&[1, 1, 3], 1), CheckOutcome::Fumble);
+        assert_eq!(outcome(&[], 0), CheckOutcome::Success);
+
+        let roll = CheckRoll::from_dice(&[4, 5, 1], 4, 3);
+        assert_eq!((roll.successes, roll.margin), (2, -1));
+    }
+
     #[test]
     fn test_check_fn() {
         let mut character = Character::default();
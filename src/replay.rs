@@ -0,0 +1,204 @@
+use std::fs;
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+use crate::data::GameData;
+use crate::game_handler::GameHandler;
+use crate::models::models::{ Ability, Character };
+use crate::state::SaveGame;
+
+/// A single input of the player.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "t", content = "c")]
+pub enum Command {
+    RoomChoice(usize),
+    BattleAction(usize),
+    EquipItem(usize),
+    ConfirmResult,
+    ResetGame,
+    SpendStatPoint(Ability),
+    NewGame(Character),
+    UseItem(usize),
+}
+
+/// The seed of a run and every command the player entered.
+/// Feeding the commands into a game with the same seed reproduces the run exactly.
+/// A recording made after loading a save game starts from that save game.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    /// The character the first run started with, the default character if not set.
+    #[serde(default)]
+    pub character: Option<Character>,
+    /// The save game the recording starts from, a new game if not set.
+    #[serde(default)]
+    pub save: Option<SaveGame>,
+    pub commands: Vec<Command>,
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Replay {
+        Replay { seed, character: None, save: None, commands: Vec::new() }
+    }
+
+    pub fn read_from_file(path: &str) -> Result<Replay, Error> {
+        let content = fs::read_to_string(path)?;
+        let parsed: Replay = serde_json::from_str(&content)?;
+        Ok(parsed)
+    }
+
+    pub fn write_to_file(&self, path: &str) -> Result<(), Error> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Starts a new game and executes all recorded commands.
+    /// Stops at the first command which fails.
+    pub fn play(&self, game_data: GameData) -> Result<GameHandler, Error> {
+        let mut game_handler = GameHandler::with_seed(game_data, self.seed);
+        match (&self.save, &self.character) {
+            (Some(save), character) => {
+                let character = character.clone().unwrap_or_else(Character::default);
+                game_handler.continue_from(save.clone(), self.seed, character)?;
+            }
+            (None, Some(character)) => game_handler.new_game(character.clone())?,
+            (None, None) => game_handler.start_game()?,
+        }
+
+        for command in &self.commands {
+            game_handler.execute_command(command.clone())?;
+        }
+
+        Ok(game_handler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::models::{ DamageRange, Item, Level, Room };
+
+    fn build_damage() -> Vec<DamageRange> {
+        serde_json::from_str(r#"[{ "dificulty": 1, "damage": "1d10" }]"#).unwrap()
+    }
+
+    fn build_game_data() -> GameData {
+        let levels: Vec<Level> = serde_json::from_str(
+            r#"[{ "name": "Test", "id": 1, "rooms": [2, 3], "level_points": 100, "first_room": 1, "final_room": 3 }]"#).unwrap();
+        let rooms: Vec<Room> = serde_json::from_str(r#"[
+            { "id": 1, "title": "A", "text": "", "choices": [{ "text": "go", "consequences": [{ "t": "GainItem", "c": 1 }] }] },
+            { "id": 2, "title": "B", "text": "", "choices": [{ "text": "jump", "consequences": [{ "t": "AbilityCheck", "c": ["Agil", 3] }] }] },
+            { "id": 3, "title": "C", "text": "", "choices": [{ "text": "climb", "consequences": [{ "t": "AbilityCheck", "c": ["Strg", 2] }] }] }
+        ]"#).unwrap();
+        let items: Vec<Item> = serde_json::from_str(r#"[{ "id": 1, "name": "Sword", "item_type": "Weapon", "strg": 1 }]"#).unwrap();
+        GameData::new(levels, rooms, items, Vec::new(), Vec::new(), Vec::new(), build_damage()).unwrap()
+    }
+
+    #[test]
+    fn test_recorded_run_is_reproduced() {
+        let mut game_handler = GameHandler::with_seed(build_game_data(), 11);
+        game_handler.start_game().unwrap();
+        game_handler.execute_room_choice(0).unwrap();
+        game_handler.confirm_result();
+        game_handler.equip_item_by_index(0).unwrap();
+        for _ in 0..5 {
+            game_handler.execute_room_choice(0).unwrap();
+            game_handler.confirm_result();
+        }
+
+        let replay = game_handler.get_replay().clone();
+        assert_eq!(replay.seed, 11);
+        assert_eq!(replay.commands.len(), 13);
+        assert_eq!(replay.commands[2], Command::EquipItem(0));
+
+        let replayed = replay.play(build_game_data()).unwrap();
+
+        let original = game_handler.state();
+        let reproduced = replayed.state();
+        assert_eq!(reproduced.character.hp, original.character.hp);
+        assert_eq!(reproduced.rooms_visited, original.rooms_visited);
+        assert_eq!(reproduced.current_room.as_ref().unwrap().id, original.current_room.as_ref().unwrap().id);
+        assert_eq!(reproduced.equipped_items.len(), 1);
+        assert_eq!(replayed.get_replay(), &replay);
+    }
+
+    #[test]
+    fn test_rejected_commands_are_not_recorded() {
+        let mut game_handler = GameHandler::with_seed(build_game_data(), 11);
+        game_handler.start_game().unwrap();
+        assert!(game_handler.equip_item_by_index(0).is_err(), "owns no item yet");
+        assert!(game_handler.execute_battle_action(0).is_err(), "not in a battle");
+        game_handler.execute_room_choice(0).unwrap();
+        assert!(game_handler.execute_room_choice(0).is_err(), "result not confirmed");
+        game_handler.confirm_result();
+        game_handler.equip_item_by_index(0).unwrap();
+
+        let replay = game_handler.get_replay().clone();
+        assert_eq!(replay.commands, vec![Command::RoomChoice(0), Command::ConfirmResult, Command::EquipItem(0)]);
+
+        let replayed = replay.play(build_game_data()).unwrap();
+        assert_eq!(replayed.state().equipped_items.len(), 1);
+        assert_eq!(replayed.get_replay(), &replay);
+    }
+
+    #[test]
+    fn test_created_characters_are_replayed() {
+        let mut first = Character::default();
+        first.name = "Kim".to_string();
+        first.agil = 4;
+        let mut second = Character::default();
+        second.name = "Sam".to_string();
+        second.strg = 4;
+
+        let mut game_handler = GameHandler::with_seed(build_game_data(), 3);
+        game_handler.new_game(first.clone()).unwrap();
+        assert_eq!(game_handler.state().character, first);
+        game_handler.new_game(second.clone()).unwrap();
+        game_handler.reset_game().unwrap();
+
+        let replay = game_handler.get_replay().clone();
+        assert_eq!(replay.character, Some(first));
+        assert_eq!(replay.commands, vec![Command::NewGame(second.clone()), Command::ResetGame]);
+
+        let replayed = replay.play(build_game_data()).unwrap();
+        assert_eq!(replayed.state().character, second);
+        assert_eq!(replayed.get_seed(), game_handler.get_seed());
+    }
+
+    #[test]
+    fn test_recording_starts_over_at_a_load() {
+        let path = std::env::temp_dir().join(format!("rusty_dungeon_replay_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let mut game_handler = GameHandler::with_seed(build_game_data(), 5);
+        game_handler.start_game().unwrap();
+        game_handler.execute_room_choice(0).unwrap();
+        game_handler.confirm_result();
+        game_handler.save_game(path).unwrap();
+        game_handler.execute_room_choice(0).unwrap();
+
+        game_handler.load_game(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_ne!(game_handler.get_seed(), 5, "reseeded on load");
+        for _ in 0..3 {
+            game_handler.execute_room_choice(0).unwrap();
+            game_handler.confirm_result();
+        }
+
+        let replay = game_handler.get_replay().clone();
+        assert!(replay.save.is_some());
+        assert_eq!(replay.commands.len(), 6, "only the commands after the load");
+
+        let replayed = replay.play(build_game_data()).unwrap();
+        assert_eq!(replayed.state().character, game_handler.state().character);
+        assert_eq!(replayed.state().rooms_visited, game_handler.state().rooms_visited);
+        assert_eq!(replayed.get_replay(), &replay);
+    }
+
+    #[test]
+    fn test_command_format() {
+        let json = serde_json::to_string(&Command::RoomChoice(2)).unwrap();
+        assert_eq!(json, r#"{"t":"RoomChoice","c":2}"#);
+    }
+}
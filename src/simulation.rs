@@ -0,0 +1,316 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use rand::prelude::*;
+
+use crate::Error;
+use crate::data::GameData;
+use crate::game_handler::GameHandler;
+use crate::models::attack_options::AttackOption;
+use crate::models::models::{ Choice, RoomResult };
+use crate::random::{ new_rng, GameRng };
+use crate::state::{ DungeonState, GameState };
+
+/// Runs are stopped after this many commands, in case the data contains a loop the policy never leaves.
+const MAX_STEPS: usize = 10_000;
+
+/// Decides what the player does during a simulated run.
+pub trait Policy {
+    /// The index of the room choice to take.
+    fn choose_room_choice(&self, rng: &mut GameRng, state: &GameState, choices: &[Choice]) -> usize;
+
+    /// The index of the attack option to use. Only called with indices of available attacks.
+    fn choose_battle_action(&self, rng: &mut GameRng, state: &GameState, available: &[usize]) -> usize {
+        let _ = (rng, state);
+        available[0]
+    }
+}
+
+/// Always takes the first choice and the first attack.
+pub struct AlwaysFirst;
+
+impl Policy for AlwaysFirst {
+    fn choose_room_choice(&self, _rng: &mut GameRng, _state: &GameState, _choices: &[Choice]) -> usize {
+        0
+    }
+}
+
+/// Picks choices and attacks at random.
+pub struct RandomChoice;
+
+impl Policy for RandomChoice {
+    fn choose_room_choice(&self, rng: &mut GameRng, _state: &GameState, choices: &[Choice]) -> usize {
+        rng.gen_range(0..choices.len())
+    }
+
+    fn choose_battle_action(&self, rng: &mut GameRng, _state: &GameState, available: &[usize]) -> usize {
+        available[rng.gen_range(0..available.len())]
+    }
+}
+
+/// Takes the choice with the most immediate rewards, avoiding hard checks and fights.
+pub struct Greedy;
+
+impl Greedy {
+    fn score(state: &GameState, choice: &Choice) -> i32 {
+        let character = state.get_effective_character();
+
+        choice.consequences.iter()
+            .chain(choice.on_success.iter())
+            .map(|consequence| match consequence {
+                RoomResult::GainLevelPoints(points) => *points as i32,
+                RoomResult::GainXp(xp) => *xp as i32,
+                RoomResult::GainItem(_) | RoomResult::GainSkill(_) => 10,
+                RoomResult::StartFight(_) => -20,
+                RoomResult::AbilityCheck(ability, dificulty) => {
+                    -10 * (*dificulty as i32 - character.get_ability(ability) as i32 / 2).max(0)
+                },
+                RoomResult::NextRoom(_) => 0,
+            })
+            .sum()
+    }
+}
+
+impl Policy for Greedy {
+    fn choose_room_choice(&self, _rng: &mut GameRng, state: &GameState, choices: &[Choice]) -> usize {
+        // max_by_key returns the last maximum, the first one is preferred on ties.
+        choices.iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|(_, choice)| Greedy::score(state, choice))
+            .map(|(index, _)| index)
+            .unwrap_or(0)
+    }
+}
+
+pub const POLICIES: &[&str] = &["random", "greedy", "first"];
+
+pub fn find_policy(name: &str) -> Result<Box<dyn Policy>, Error> {
+    match name {
+        "random" => Ok(Box::new(RandomChoice)),
+        "greedy" => Ok(Box::new(Greedy)),
+        "first" => Ok(Box::new(AlwaysFirst)),
+        _ => Err(Error::GameDataError(format!("unknown policy {}, expected one of {}", name, POLICIES.join(", ")))),
+    }
+}
+
+/// How often checks of one difficulty were made and how much hp they cost.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CheckStats {
+    pub checks: u32,
+    pub failures: u32,
+    pub hp_lost: u64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SimulationReport {
+    pub runs: u32,
+    pub wins: u32,
+    pub defeats: u32,
+    /// Runs stopped after `MAX_STEPS` commands.
+    pub unfinished: u32,
+    pub xp_gained: u64,
+    pub checks: BTreeMap<u8, CheckStats>,
+    pub room_visits: BTreeMap<u16, u32>,
+}
+
+impl SimulationReport {
+    pub fn win_rate(&self) -> f64 {
+        ratio(self.wins as u64, self.runs)
+    }
+
+    pub fn average_xp(&self) -> f64 {
+        ratio(self.xp_gained, self.runs)
+    }
+}
+
+fn ratio(amount: u64, total: u32) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        amount as f64 / total as f64
+    }
+}
+
+impl fmt::Display for SimulationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Runs: {}", self.runs)?;
+        writeln!(f, "Wins: {} ({:.1}%), defeats: {}, unfinished: {}",
+            self.wins, self.win_rate() * 100.0, self.defeats, self.unfinished)?;
+        writeln!(f, "Average XP gained: {:.1}", self.average_xp())?;
+
+        writeln!(f, "Ability checks:")?;
+        for (dificulty, stats) in &self.checks {
+            writeln!(f, "  difficulty {}: {} checks, {:.1}% failed, {:.2} hp lost per check",
+                dificulty, stats.checks,
+                ratio(stats.failures as u64, stats.checks) * 100.0,
+                ratio(stats.hp_lost, stats.checks))?;
+        }
+
+        writeln!(f, "Room visits per run:")?;
+        for (room_id, visits) in &self.room_visits {
+            writeln!(f, "  room {}: {:.2}", room_id, ratio(*visits as u64, self.runs))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Plays `runs` games with the given policy and collects their statistics.
+/// Run `n` is played with the seed `seed + n`, so a simulation can be repeated exactly.
+/// With a `level_id` every run starts in that level and is won once the level is completed,
+/// otherwise the whole campaign has to be completed.
+pub fn simulate(game_data: &GameData, policy: &dyn Policy, runs: u32, seed: u64, level_id: Option<u16>) -> Result<SimulationReport, Error> {
+    let mut report = SimulationReport::default();
+    let mut rng = new_rng(seed);
+
+    for run in 0..runs {
+        let mut game_handler = GameHandler::with_seed(game_data.clone(), seed.wrapping_add(run as u64));
+        match level_id {
+            Some(level_id) => game_handler.start_level(level_id)?,
+            None => game_handler.start_game()?,
+        }
+        play_run(&mut game_handler, policy, &mut rng, level_id, &mut report)?;
+    }
+
+    Ok(report)
+}
+
+fn play_run(game_handler: &mut GameHandler, policy: &dyn Policy, rng: &mut GameRng, level_id: Option<u16>, report: &mut SimulationReport) -> Result<(), Error> {
+    let mut rooms_visited = 0;
+
+    report.runs += 1;
+
+    for _ in 0..MAX_STEPS {
+        let gs = game_handler.state();
+
+        if gs.rooms_visited != rooms_visited {
+            rooms_visited = gs.rooms_visited;
+            if let Some(room) = &gs.current_room {
+                *report.room_visits.entry(room.id).or_insert(0) += 1;
+            }
+        }
+
+        let level_left = match (level_id, &gs.current_level) {
+            (Some(level_id), Some(level)) => level.id != level_id,
+            _ => false,
+        };
+
+        match gs.dungeon_state {
+            DungeonState::CampaignComplete => {
+                report.wins += 1;
+                report.xp_gained += gs.character.xp as u64;
+                return Ok(());
+            },
+            DungeonState::GameOver => {
+                report.defeats += 1;
+                report.xp_gained += gs.character.xp as u64;
+                return Ok(());
+            },
+            _ if level_left => {
+                report.wins += 1;
+                report.xp_gained += gs.character.xp as u64;
+                return Ok(());
+            },
+            DungeonState::Result | DungeonState::Failure => {
+                drop(gs);
+                game_handler.confirm_result();
+            },
+            DungeonState::Encounter => {
+                let available: Vec<usize> = gs.get_attack_options().iter()
+                    .enumerate()
+                    .filter(|(_, option)| matches!(option, AttackOption::Attack(_)))
+                    .map(|(index, _)| index)
+                    .collect();
+                if available.is_empty() {
+                    return Err(Error::GameDataError("The player has no attack to use in a battle.".to_string()));
+                }
+                let index = policy.choose_battle_action(rng, &gs, &available);
+                drop(gs);
+                game_handler.execute_battle_action(index)?;
+            },
+            DungeonState::Room => {
+                let choices = match &gs.current_room {
+                    Some(room) if !room.choices.is_empty() => room.choices.clone(),
+                    _ => return Err(Error::GameDataError("The current room has no choices.".to_string())),
+                };
+                let index = policy.choose_room_choice(rng, &gs, &choices);
+                drop(gs);
+                game_handler.execute_room_choice(index)?;
+                record_checks(&game_handler.state(), report);
+            },
+        }
+    }
+
+    report.unfinished += 1;
+    report.xp_gained += game_handler.state().character.xp as u64;
+    Ok(())
+}
+
+fn record_checks(state: &GameState, report: &mut SimulationReport) {
+    for check in &state.last_checks {
+        let stats = report.checks.entry(check.dificulty).or_default();
+        stats.checks += 1;
+        stats.hp_lost += check.damage as u64;
+        if !check.roll.outcome.is_success() {
+            stats.failures += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::models::{ DamageRange, Level, Room };
+
+    fn build_damage() -> Vec<DamageRange> {
+        serde_json::from_str(r#"[{ "dificulty": 1, "damage": "1d10" }]"#).unwrap()
+    }
+
+    fn build_game_data() -> GameData {
+        let levels: Vec<Level> = serde_json::from_str(
+            r#"[{ "name": "Test", "id": 1, "rooms": [2], "level_points": 2, "first_room": 1, "final_room": 3 }]"#).unwrap();
+        let rooms: Vec<Room> = serde_json::from_str(r#"[
+            { "id": 1, "title": "A", "text": "", "choices": [
+                { "text": "jump", "consequences": [{ "t": "AbilityCheck", "c": ["Agil", 5] }, { "t": "GainLevelPoints", "c": 1 }] },
+                { "text": "walk", "consequences": [{ "t": "GainXp", "c": 5 }, { "t": "GainLevelPoints", "c": 1 }] }
+            ] },
+            { "id": 2, "title": "B", "text": "", "choices": [{ "text": "go", "consequences": [{ "t": "GainLevelPoints", "c": 1 }] }] },
+            { "id": 3, "title": "C", "text": "", "choices": [{ "text": "leave", "consequences": [] }] }
+        ]"#).unwrap();
+        GameData::new(levels, rooms, Vec::new(), Vec::new(), Vec::new(), Vec::new(), build_damage()).unwrap()
+    }
+
+    #[test]
+    fn test_always_first() {
+        let report = simulate(&build_game_data(), &AlwaysFirst, 20, 1, None).unwrap();
+
+        assert_eq!(report.runs, 20);
+        assert_eq!(report.wins + report.defeats + report.unfinished, 20);
+        assert_eq!(report.checks.get(&5).unwrap().checks, 20, "every run jumps once");
+        assert_eq!(report.room_visits.get(&1), Some(&20));
+    }
+
+    #[test]
+    fn test_greedy_avoids_checks() {
+        let report = simulate(&build_game_data(), &Greedy, 20, 1, None).unwrap();
+
+        assert_eq!(report.wins, 20);
+        assert!(report.checks.is_empty());
+        assert_eq!(report.average_xp(), 5.0);
+        assert_eq!(report.room_visits.get(&3), Some(&20));
+    }
+
+    #[test]
+    fn test_simulation_is_repeatable() {
+        let first = simulate(&build_game_data(), &RandomChoice, 50, 7, Some(1)).unwrap();
+        let second = simulate(&build_game_data(), &RandomChoice, 50, 7, Some(1)).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_find_policy() {
+        assert!(POLICIES.iter().all(|name| find_policy(name).is_ok()));
+        assert!(find_policy("lucky").is_err());
+    }
+}
@@ -1,7 +1,11 @@
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum DungeonState {
     Room,
     Encounter,
     Result,
     Failure,
-}
\ No newline at end of file
+    CampaignComplete,
+    GameOver,
+}
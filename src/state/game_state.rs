@@ -2,41 +2,64 @@ use crate::Error;
 use crate::models::models::Reward;
 use crate::state::DungeonState;
 use std::rc::Rc;
-use crate::models::models::{ Level, Room, Item, Skill, Character, BadResult };
+use crate::models::models::{ Level, Room, Item, Skill, Character, CharacterClass, BadResult, CheckResult, SkillModifier, ItemType };
 use crate::models::attack_options::*;
+use crate::models::{ ActorValues, BattleEvents };
 
+/// A stack of equal items the player carries.
+#[derive(Clone)]
+pub struct OwnedItem {
+    pub item: Rc<Item>,
+    pub quantity: u16,
+}
 
 pub struct GameState {
+    pub seed: u64,
     pub level_points: u16,
+    pub final_room_entered: bool,
+    pub rooms_visited: u16,
     pub dungeon_state: DungeonState,
     pub current_level: Option<Rc<Level>>,
     pub current_room: Option<Rc<Room>>,
-    pub owned_items: Vec<Rc<Item>>,
+    pub owned_items: Vec<OwnedItem>,
     pub equipped_items: Vec<Rc<Item>>,
     pub gained_skills: Vec<Rc<Skill>>,
     pub character: Character,
+    /// The class of `character`, looked up from the game data.
+    pub character_class: Option<Rc<CharacterClass>>,
     pub last_rewards: Vec<Reward>,
     pub last_bad_results: Vec<BadResult>,
+    pub last_checks: Vec<CheckResult>,
+    pub battle_actors: Vec<ActorValues>,
+    pub battle_events: BattleEvents,
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        GameState::new()
+    }
 }
 
 impl GameState {
     pub fn new() -> GameState {
-        let mut character = Character::default();
-        character.strg = 2;
-        character.agil = 2;
-        character.def = 2;
-
         GameState {
+            seed: 0,
             level_points: 0,
+            final_room_entered: false,
+            rooms_visited: 0,
             dungeon_state: DungeonState::Room,
             current_level: None,
             current_room: None,
             owned_items: Vec::new(),
             equipped_items: Vec::new(),
             gained_skills: Vec::new(),
-            character,
+            character: Character::default(),
+            character_class: None,
             last_rewards: Vec::new(),
             last_bad_results: Vec::new(),
+            last_checks: Vec::new(),
+            battle_actors: Vec::new(),
+            battle_events: BattleEvents::new(),
         }
     }
 
@@ -49,12 +72,35 @@ impl GameState {
             Some(level) => {
                 Ok((**level).clone())
             },
-            None => Err(Error::GameDataError(format!("Trying to access empty current_level")))
+            None => Err(Error::GameDataError("Trying to access empty current_level".to_string()))
         }
     }
 
     pub fn set_current_room(&mut self, room: &Rc<Room>) {
         self.current_room = Some(Rc::clone(room));
+        self.rooms_visited += 1;
+    }
+
+    /// Adds the item to the stack of equal items, or starts a new stack.
+    pub fn add_item(&mut self, item: &Rc<Item>) {
+        match self.owned_items.iter_mut().find(|owned| owned.item.id == item.id) {
+            Some(owned) => owned.quantity += 1,
+            None => self.owned_items.push(OwnedItem { item: Rc::clone(item), quantity: 1 }),
+        }
+    }
+
+    /// Takes one item off the stack at the index, the stack is removed once it is empty.
+    pub fn remove_item(&mut self, index: usize) {
+        if let Some(owned) = self.owned_items.get_mut(index) {
+            owned.quantity -= 1;
+            if owned.quantity == 0 {
+                self.owned_items.remove(index);
+            }
+        }
+    }
+
+    pub fn heal(&mut self, hp: u16) {
+        self.character.hp = self.character.hp.saturating_add(hp).min(self.character.hp_max);
     }
 
     pub fn remove_hp(&mut self, hp: u16) {
@@ -65,22 +111,178 @@ impl GameState {
         }
     }
 
+    /// The character with all bonuses of equipped items and gained skills applied.
+    /// Ability increases are added first, multipliers are applied to the sum.
+    pub fn get_effective_character(&self) -> Character {
+        let mut character = self.character.clone();
+
+        for item in &self.equipped_items {
+            character.strg = character.strg.saturating_add(item.strg);
+            character.agil = character.agil.saturating_add(item.agil);
+            character.def = character.def.saturating_add(item.def);
+        }
+
+        let modifiers: Vec<&SkillModifier> = self.gained_skills.iter()
+            .flat_map(|skill| skill.modifiers.iter())
+            .collect();
+
+        for modifier in &modifiers {
+            if let SkillModifier::AbilityIncrease(ability, amount) = modifier {
+                let value = character.get_ability_mut(ability);
+                *value = value.saturating_add(u16::from(*amount));
+            }
+        }
+
+        for modifier in &modifiers {
+            if let SkillModifier::AbilityMulti(ability, factor) = modifier {
+                let value = character.get_ability_mut(ability);
+                *value = value.saturating_mul(u16::from(*factor));
+            }
+        }
+
+        character
+    }
+
+    /// The attacks of the equipped weapon, or an unarmed attack without one, followed by the attacks of the
+    /// class and the gained skills. Slots without an attack are `AttackOption::None`.
     pub fn get_attack_options(&self) -> AttackOptions {
-        let mut options: AttackOptions = Vec::new();
+        let mut attacks: Vec<AttackDescription> = self.equipped_items.iter()
+            .filter(|item| item.item_type == ItemType::Weapon)
+            .flat_map(|item| item.attacks.iter().cloned())
+            .collect();
+
+        if attacks.is_empty() {
+            attacks.push(AttackDescription {
+                title: "Unarmed".to_owned(),
+                attack_type: "physical".to_owned(),
+                dmg_min: 1,
+                dmg_max: 3,
+                special_effect: "".to_owned(),
+                status_effects: Vec::new(),
+            });
+        }
+
+        if let Some(class) = &self.character_class {
+            attacks.extend(class.attacks.iter().cloned());
+        }
+        attacks.extend(self.gained_skills.iter().flat_map(|skill| skill.attacks.iter().cloned()));
 
-        options.push(AttackOption::Attack(AttackDescription {
-            title: "Unarmed".to_owned(),
+        let mut options: AttackOptions = attacks.into_iter()
+            .take(ATTACK_SLOTS)
+            .map(AttackOption::Attack)
+            .collect();
+        options.resize(ATTACK_SLOTS, AttackOption::None);
+        options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::models::{ Ability, ItemType };
+
+    fn build_item(id: u16, strg: u16, def: u16) -> Rc<Item> {
+        Rc::new(Item {
+            id,
+            name: "Item".to_owned(),
+            item_type: ItemType::Weapon,
+            strg,
+            agil: 0,
+            def,
+            attacks: Vec::new(),
+            effects: Vec::new(),
+        })
+    }
+
+    fn build_skill(id: u16, modifiers: Vec<SkillModifier>) -> Rc<Skill> {
+        Rc::new(Skill {
+            id,
+            name: "Skill".to_owned(),
+            modifiers,
+            attacks: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn test_effective_character_without_bonuses() {
+        let state = GameState::new();
+        let character = state.get_effective_character();
+        assert_eq!(character.strg, state.character.strg);
+        assert_eq!(character.agil, state.character.agil);
+        assert_eq!(character.def, state.character.def);
+    }
+
+    #[test]
+    fn test_effective_character_with_items() {
+        let mut state = GameState::new();
+        state.equipped_items.push(build_item(1, 3, 0));
+        state.equipped_items.push(build_item(2, 0, 2));
+
+        let character = state.get_effective_character();
+        assert_eq!(character.strg, 5);
+        assert_eq!(character.agil, 2);
+        assert_eq!(character.def, 4);
+    }
+
+    #[test]
+    fn test_effective_character_with_skills() {
+        let mut state = GameState::new();
+        state.equipped_items.push(build_item(1, 1, 0));
+        state.gained_skills.push(build_skill(1, vec![SkillModifier::AbilityMulti(Ability::Strg, 3)]));
+        state.gained_skills.push(build_skill(2, vec![
+            SkillModifier::AbilityIncrease(Ability::Strg, 1),
+            SkillModifier::AbilityIncrease(Ability::Agil, 2),
+        ]));
+
+        let character = state.get_effective_character();
+        assert_eq!(character.strg, 12, "(2 + 1 + 1) * 3");
+        assert_eq!(character.agil, 4);
+        assert_eq!(character.def, 2);
+    }
+
+    #[test]
+    fn test_effective_character_saturates() {
+        let mut state = GameState::new();
+        state.equipped_items.push(build_item(1, u16::MAX, 0));
+        state.gained_skills.push(build_skill(1, vec![
+            SkillModifier::AbilityIncrease(Ability::Strg, 1),
+            SkillModifier::AbilityMulti(Ability::Strg, 2),
+        ]));
+
+        assert_eq!(state.get_effective_character().strg, u16::MAX);
+    }
+
+    fn build_attack(title: &str) -> AttackDescription {
+        AttackDescription {
+            title: title.to_owned(),
             attack_type: "physical".to_owned(),
             dmg_min: 1,
-            dmg_max: 3,
+            dmg_max: 4,
             special_effect: "".to_owned(),
-        }));
+            status_effects: Vec::new(),
+        }
+    }
 
-        options.push(AttackOption::None);
-        options.push(AttackOption::None);
-        options.push(AttackOption::None);
+    fn attack_titles(state: &GameState) -> Vec<String> {
+        state.get_attack_options().iter().map(|option| match option {
+            AttackOption::Attack(description) => description.title.to_owned(),
+            AttackOption::None => "-".to_owned(),
+        }).collect()
+    }
 
-        options
+    #[test]
+    fn test_attack_options() {
+        let mut state = GameState::new();
+        assert_eq!(attack_titles(&state), vec!["Unarmed", "-", "-", "-"]);
+
+        let mut sword = (*build_item(1, 0, 0)).clone();
+        sword.attacks = vec![build_attack("Slash"), build_attack("Thrust")];
+        state.equipped_items.push(Rc::new(sword));
+        assert_eq!(attack_titles(&state), vec!["Slash", "Thrust", "-", "-"]);
+
+        let mut skill = (*build_skill(1, Vec::new())).clone();
+        skill.attacks = vec![build_attack("Whirlwind"), build_attack("Kick"), build_attack("Headbutt")];
+        state.gained_skills.push(Rc::new(skill));
+        assert_eq!(attack_titles(&state), vec!["Slash", "Thrust", "Whirlwind", "Kick"], "only four slots");
     }
 }
-
@@ -1,5 +1,7 @@
 mod game_state;
 mod dungeon_state;
+mod save_game;
 
 pub use game_state::*;
-pub use dungeon_state::*;
\ No newline at end of file
+pub use dungeon_state::*;
+pub use save_game::*;
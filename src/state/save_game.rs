@@ -0,0 +1,186 @@
+use std::fs;
+use std::rc::Rc;
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+use crate::data::GameData;
+use crate::models::models::Character;
+use crate::state::{ DungeonState, GameState };
+
+pub const SAVE_PATH: &str = "./save.json";
+
+/// Everything needed to continue a run. Game data is referenced by id
+/// and looked up again when the save game is loaded.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SaveGame {
+    pub level_id: Option<u16>,
+    pub room_id: Option<u16>,
+    /// One id per owned item, a stack of items repeats its id.
+    pub owned_item_ids: Vec<u16>,
+    pub equipped_item_ids: Vec<u16>,
+    pub skill_ids: Vec<u16>,
+    pub character: Character,
+    pub level_points: u16,
+    pub final_room_entered: bool,
+    #[serde(default)]
+    pub rooms_visited: u16,
+    pub dungeon_state: DungeonState,
+}
+
+impl SaveGame {
+    pub fn read_from_file(path: &str) -> Result<SaveGame, Error> {
+        let content = fs::read_to_string(path)?;
+        let parsed: SaveGame = serde_json::from_str(&content)?;
+        Ok(parsed)
+    }
+
+    pub fn write_to_file(&self, path: &str) -> Result<(), Error> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Rebuilds the game state. Fails if any of the referenced ids do not exist in the game data.
+    pub fn into_game_state(self, game_data: &GameData) -> Result<GameState, Error> {
+        let mut state = GameState::new();
+
+        if let Some(level_id) = self.level_id {
+            let level = game_data.find_level_by_id(level_id)
+                .map_err(|_| missing_id_error("level", level_id))?;
+            state.set_current_level(level);
+        }
+
+        if let Some(room_id) = self.room_id {
+            let room = game_data.find_room_by_id(room_id)
+                .map_err(|_| missing_id_error("room", room_id))?;
+            state.set_current_room(room);
+        }
+
+        for id in self.owned_item_ids {
+            let item = game_data.find_item_by_id(id).map_err(|_| missing_id_error("item", id))?;
+            state.add_item(item);
+        }
+
+        for id in self.equipped_item_ids {
+            let item = game_data.find_item_by_id(id).map_err(|_| missing_id_error("item", id))?;
+            state.equipped_items.push(Rc::clone(item));
+        }
+
+        for id in self.skill_ids {
+            let skill = game_data.find_skill_by_id(id).map_err(|_| missing_id_error("skill", id))?;
+            state.gained_skills.push(skill);
+        }
+
+        if let Some(class_id) = self.character.class {
+            let class = game_data.find_class_by_id(class_id).map_err(|_| missing_id_error("class", class_id))?;
+            state.character_class = Some(class);
+        }
+
+        state.character = self.character;
+        state.level_points = self.level_points;
+        state.final_room_entered = self.final_room_entered;
+        state.rooms_visited = self.rooms_visited;
+        state.dungeon_state = self.dungeon_state;
+
+        Ok(state)
+    }
+}
+
+impl From<&GameState> for SaveGame {
+    fn from(state: &GameState) -> Self {
+        SaveGame {
+            level_id: state.current_level.as_ref().map(|level| level.id),
+            room_id: state.current_room.as_ref().map(|room| room.id),
+            owned_item_ids: state.owned_items.iter()
+                .flat_map(|owned| vec![owned.item.id; owned.quantity.into()])
+                .collect(),
+            equipped_item_ids: state.equipped_items.iter().map(|item| item.id).collect(),
+            skill_ids: state.gained_skills.iter().map(|skill| skill.id).collect(),
+            character: state.character.clone(),
+            level_points: state.level_points,
+            final_room_entered: state.final_room_entered,
+            rooms_visited: state.rooms_visited,
+            // The results of the last choice are not saved, a loaded game continues in the room.
+            dungeon_state: match state.dungeon_state {
+                DungeonState::Result | DungeonState::Failure => DungeonState::Room,
+                ref dungeon_state => dungeon_state.clone(),
+            },
+        }
+    }
+}
+
+fn missing_id_error(kind: &str, id: u16) -> Error {
+    Error::SaveGameError(format!("{} with id {} no longer exists", kind, id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::models::{ Item, Level, Room, Skill };
+
+    fn build_game_data() -> GameData {
+        let levels: Vec<Level> = serde_json::from_str(
+            r#"[{ "name": "Test", "id": 1, "rooms": [], "level_points": 10, "first_room": 1, "final_room": 1 }]"#).unwrap();
+        let rooms: Vec<Room> = serde_json::from_str(
+            r#"[{ "id": 1, "title": "A", "text": "", "choices": [] }]"#).unwrap();
+        let items: Vec<Item> = serde_json::from_str(
+            r#"[{ "id": 1, "name": "Sword", "item_type": "Weapon" }, { "id": 2, "name": "Armor", "item_type": "Armor" }]"#).unwrap();
+        let skills: Vec<Skill> = serde_json::from_str(
+            r#"[{ "id": 1, "name": "Strong", "modifiers": [] }]"#).unwrap();
+        GameData::new(levels, rooms, items, skills, Vec::new(), Vec::new(), Vec::new()).unwrap()
+    }
+
+    fn build_save_game() -> SaveGame {
+        SaveGame {
+            level_id: Some(1),
+            room_id: Some(1),
+            owned_item_ids: vec![1, 2, 2],
+            equipped_item_ids: vec![2],
+            skill_ids: vec![1],
+            character: Character::default(),
+            level_points: 5,
+            final_room_entered: false,
+            rooms_visited: 3,
+            dungeon_state: DungeonState::Room,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let game_data = build_game_data();
+        let state = build_save_game().into_game_state(&game_data).unwrap();
+
+        assert_eq!(state.owned_items.len(), 2);
+        assert_eq!(state.owned_items[1].quantity, 2);
+        assert_eq!(state.equipped_items[0].id, 2);
+        assert_eq!(state.gained_skills[0].id, 1);
+        assert_eq!(state.level_points, 5);
+        assert_eq!(state.rooms_visited, 3);
+
+        let save = SaveGame::from(&state);
+        assert_eq!(save.level_id, Some(1));
+        assert_eq!(save.room_id, Some(1));
+        assert_eq!(save.owned_item_ids, vec![1, 2, 2]);
+        assert_eq!(save.equipped_item_ids, vec![2]);
+        assert_eq!(save.skill_ids, vec![1]);
+    }
+
+    #[test]
+    fn test_result_screen_is_not_saved() {
+        let mut state = build_save_game().into_game_state(&build_game_data()).unwrap();
+        state.dungeon_state = DungeonState::Failure;
+
+        assert!(matches!(SaveGame::from(&state).dungeon_state, DungeonState::Room));
+    }
+
+    #[test]
+    fn test_missing_id() {
+        let mut save = build_save_game();
+        save.owned_item_ids.push(7);
+
+        match save.into_game_state(&build_game_data()) {
+            Err(Error::SaveGameError(message)) => assert_eq!(message, "item with id 7 no longer exists"),
+            _ => panic!("expected a save game error"),
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use crate::models::models::{ Ability, Character, CharacterClass };
+use crate::character_creation::{ CharacterDraft, STAT_BUDGET, MAX_STAT };
+use crate::random::{ new_rng, GameRng };
+use crate::GameHandler;
+use crate::Error;
+use rand::prelude::*;
+use std::rc::Rc;
+use tui::text::Span;
+use tui::text::Spans;
+use tui::widgets::{Paragraph, Wrap, Block, Borders, BorderType};
+use tui::style::{Style, Modifier, Color};
+use tui::layout::{Layout, Rect, Alignment, Direction, Constraint};
+use tui::backend::Backend;
+use tui::Frame;
+use crossterm::event::KeyCode;
+
+const ROWS: usize = 5;
+
+/// The new game screen: the player names the character and spreads the stat budget.
+pub struct CharacterCreationView {
+    draft: CharacterDraft,
+    classes: Vec<Rc<CharacterClass>>,
+    /// 0 is the name, 1 the class and 2 to 4 are the abilities.
+    selected: usize,
+    can_cancel: bool,
+    message: Option<String>,
+    rng: GameRng,
+}
+
+impl CharacterCreationView {
+    /// Starts with the name, class and abilities of the given character, or the first class if it has none.
+    /// Only a screen that can be cancelled returns to the current run on Esc.
+    pub fn new(character: &Character, classes: &[Rc<CharacterClass>], can_cancel: bool) -> CharacterCreationView {
+        let class = classes.iter()
+            .find(|class| Some(class.id) == character.class)
+            .or_else(|| classes.first())
+            .cloned();
+
+        CharacterCreationView {
+            draft: CharacterDraft::new(character, class),
+            classes: classes.to_vec(),
+            selected: 0,
+            can_cancel,
+            message: None,
+            // Rolling only changes the draft, the created character itself is part of a replay.
+            rng: new_rng(thread_rng().gen()),
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame<impl Backend>, rect: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(2), Constraint::Length(5)].as_ref())
+            .split(rect);
+
+        let title = Paragraph::new("New Character")
+            .style(Style::default().add_modifier(Modifier::BOLD))
+            .alignment(Alignment::Center)
+            .block(self.build_block());
+
+        frame.render_widget(title, chunks[0]);
+        frame.render_widget(self.build_form(), chunks[1]);
+        frame.render_widget(self.build_help(), chunks[2]);
+    }
+
+    fn build_form(&self) -> Paragraph {
+        let name_style = self.row_style(0).fg(Color::Green);
+        let mut content = vec![
+            Spans::from(vec![
+                Span::raw(self.marker(0)),
+                Span::raw("Name: "),
+                Span::styled(format!("{}_", self.draft.character.name), name_style),
+            ]),
+        ];
+        content.append(&mut self.build_class_lines());
+        content.append(&mut vec![
+            Spans::from(""),
+            self.build_ability_line(2, "Strength: ", &Ability::Strg),
+            self.build_ability_line(3, "Agility: ", &Ability::Agil),
+            self.build_ability_line(4, "Defence: ", &Ability::Def),
+            Spans::from(""),
+            Spans::from(vec![
+                Span::raw("Points left: "),
+                Span::styled(
+                    format!("{}/{}", self.draft.points_left(), STAT_BUDGET),
+                    Style::default().fg(Color::LightMagenta)),
+            ]),
+        ]);
+
+        if let Some(message) = &self.message {
+            content.push(Spans::from(""));
+            content.push(Spans::from(Span::styled(message.to_owned(), Style::default().fg(Color::Yellow))));
+        }
+
+        Paragraph::new(content)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .block(self.build_block())
+    }
+
+    fn build_class_lines(&self) -> Vec<Spans> {
+        let class = match self.draft.get_class() {
+            Some(class) => class,
+            None => return Vec::new(),
+        };
+        let attacks: Vec<&str> = class.attacks.iter().map(|attack| attack.title.as_str()).collect();
+
+        vec![
+            Spans::from(vec![
+                Span::raw(self.marker(1)),
+                Span::raw("Class: "),
+                Span::styled("< ", Style::default().fg(Color::Yellow)),
+                Span::styled(class.name.to_owned(), self.row_style(1).fg(Color::LightCyan)),
+                Span::styled(" >", Style::default().fg(Color::Yellow)),
+            ]),
+            Spans::from(Span::styled(class.description.to_owned(), Style::default().fg(Color::DarkGray))),
+            Spans::from(vec![
+                Span::raw("Health: "),
+                Span::styled(format!("{}", class.hp_max), Style::default().fg(Color::Green)),
+                Span::raw("  Attacks: "),
+                Span::styled(attacks.join(", "), Style::default().fg(Color::LightRed)),
+            ]),
+        ]
+    }
+
+    fn build_ability_line(&self, row: usize, label: &'static str, ability: &Ability) -> Spans {
+        let value = self.draft.character.get_ability(ability);
+        let left = if value > self.draft.minimum(ability) { "< " } else { "  " };
+        let right = if value < MAX_STAT { " >" } else { "  " };
+
+        Spans::from(vec![
+            Span::raw(self.marker(row)),
+            Span::raw(label),
+            Span::styled(left, Style::default().fg(Color::Yellow)),
+            Span::styled(format!("{}", value), self.row_style(row).fg(Color::Green)),
+            Span::styled(right, Style::default().fg(Color::Yellow)),
+        ])
+    }
+
+    fn build_help(&self) -> Paragraph {
+        let mut content = vec![
+            Span::styled("[Up/Down]", Style::default().fg(Color::Yellow)),
+            Span::raw(" Select  "),
+            Span::styled("[Left/Right]", Style::default().fg(Color::Yellow)),
+            Span::raw(" Change  "),
+            Span::styled("[F2]", Style::default().fg(Color::Yellow)),
+            Span::raw(" Roll  "),
+            Span::styled("[Enter]", Style::default().fg(Color::Yellow)),
+            Span::raw(" Start"),
+        ];
+        if self.can_cancel {
+            content.push(Span::styled("  [Esc]", Style::default().fg(Color::Yellow)));
+            content.push(Span::raw(" Back"));
+        }
+
+        Paragraph::new(Spans::from(content))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .style(Style::default().fg(Color::White))
+                    .border_type(BorderType::Rounded),
+            )
+    }
+
+    fn marker(&self, row: usize) -> &'static str {
+        if row == self.selected { "> " } else { "  " }
+    }
+
+    fn row_style(&self, row: usize) -> Style {
+        if row == self.selected {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        }
+    }
+
+    fn build_block(&self) -> Block {
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White))
+            .border_type(BorderType::Double)
+    }
+
+    fn selected_ability(&self) -> Option<Ability> {
+        match self.selected {
+            2 => Some(Ability::Strg),
+            3 => Some(Ability::Agil),
+            4 => Some(Ability::Def),
+            _ => None,
+        }
+    }
+
+    /// Picks the next or previous class.
+    fn cycle_class(&mut self, forward: bool) {
+        let count = self.classes.len();
+        let current = self.draft.get_class()
+            .and_then(|class| self.classes.iter().position(|other| other.id == class.id));
+        let next = match current {
+            Some(index) if forward => (index + 1) % count,
+            Some(index) => (index + count - 1) % count,
+            None if count > 0 => 0,
+            None => return,
+        };
+        self.draft.set_class(Some(self.classes[next].clone()));
+    }
+
+    fn next_row(&self, row: usize, forward: bool) -> usize {
+        let row = if forward { (row + 1) % ROWS } else { (row + ROWS - 1) % ROWS };
+        // Without classes in the game data there is nothing to pick.
+        if row == 1 && self.classes.is_empty() {
+            return self.next_row(row, forward);
+        }
+        row
+    }
+
+    /// Returns false once the screen is done, either with a started game or cancelled.
+    pub fn handle_input(&mut self, key_code: KeyCode, game_handler: &mut GameHandler) -> Result<bool, Error> {
+        self.message = None;
+
+        match key_code {
+            KeyCode::Up => self.selected = self.next_row(self.selected, false),
+            KeyCode::Down | KeyCode::Tab => self.selected = self.next_row(self.selected, true),
+            KeyCode::Left if self.selected == 1 => self.cycle_class(false),
+            KeyCode::Right if self.selected == 1 => self.cycle_class(true),
+            KeyCode::Esc if self.can_cancel => return Ok(false),
+            // Not a character key, typing a name never rolls.
+            KeyCode::F(2) => self.draft.roll(&mut self.rng),
+            KeyCode::Enter => {
+                match self.draft.build() {
+                    Ok(character) => {
+                        game_handler.new_game(character)?;
+                        return Ok(false);
+                    }
+                    Err(Error::CharacterError(message)) => self.message = Some(message),
+                    Err(error) => return Err(error),
+                }
+            }
+            KeyCode::Backspace if self.selected == 0 => self.draft.pop_char(),
+            KeyCode::Char(c) if self.selected == 0 => self.draft.push_char(c),
+            _ => {
+                if let Some(ability) = self.selected_ability() {
+                    match key_code {
+                        KeyCode::Left => { self.draft.decrease(&ability); }
+                        KeyCode::Right => { self.draft.increase(&ability); }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        Ok(true)
+    }
+}
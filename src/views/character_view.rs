@@ -1,4 +1,4 @@
-use crate::models::models::Character;
+use crate::models::models::{ Ability, Character };
 use std::rc::Rc;
 use crate::Skill;
 use crate::GameHandler;
@@ -13,41 +13,83 @@ use crossterm::event::KeyCode;
 
 use crate::Error;
 use crate::GameState;
+use crate::views::components::LevelProgress;
+use crate::leveling::xp_for_level;
 
 pub struct CharacterView {}
 
 impl CharacterView {
     pub fn render(&self, frame: &mut Frame<impl Backend>, rect: Rect, game_state: &GameState) -> Result<(), String> {
-        let Character { xp, hp, hp_max, .. } = game_state.character;
-        let overview = self.build_overview(xp, hp, hp_max);
+        let class_name = game_state.character_class.as_ref().map(|class| class.name.as_str());
+        let overview = self.build_overview(&game_state.character, class_name, &game_state.get_effective_character());
 
         let skills = self.build_skill_section(&game_state.gained_skills[..]);
 
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([Constraint::Length(25), Constraint::Min(25)])
+            .constraints([Constraint::Length(30), Constraint::Min(25)])
             .split(rect);
 
-        frame.render_widget(overview, chunks[0]);
+        let overview_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(2), Constraint::Length(3)])
+            .split(chunks[0]);
+
+        frame.render_widget(overview, overview_chunks[0]);
         frame.render_widget(skills, chunks[1]);
 
+        if let Ok(level) = game_state.get_current_level() {
+            let progress = LevelProgress::new(game_state.level_points, level.level_points);
+            frame.render_widget(progress, overview_chunks[1]);
+        }
+
         Ok(())
     }
 
-    fn build_overview(&self, xp: u16, hp: u16, hp_max: u16) -> Paragraph {
-        let content = vec![
+    fn build_overview(&self, base: &Character, class_name: Option<&str>, effective: &Character) -> Paragraph {
+        let Character { xp, hp, hp_max, level, stat_points, .. } = *base;
+        let mut title = vec![
+            Span::styled(base.name.to_owned(), Style::default().add_modifier(Modifier::BOLD))
+        ];
+        if let Some(class_name) = class_name {
+            title.push(Span::raw(" the "));
+            title.push(Span::styled(class_name.to_owned(), Style::default().fg(Color::LightCyan)));
+        }
+
+        let mut content = vec![
+            Spans::from(title),
             Spans::from(vec![
-                Span::styled("Character", Style::default().add_modifier(Modifier::BOLD))
+                Span::raw("Level: "),
+                Span::styled(format!("{}", level), Style::default().fg(Color::Green)),
             ]),
             Spans::from(vec![
                 Span::raw("Experience Points: "),
-                Span::styled(format!("{}", xp), Style::default().fg(Color::Green)),
+                Span::styled(format!("{}/{}", xp, xp_for_level(level + 1)), Style::default().fg(Color::Green)),
             ]),
             Spans::from(vec![
                 Span::raw("Health: "),
                 Span::styled(format!("{}/{}", hp, hp_max), Style::default().fg(Color::Green)),
             ]),
+            self.build_ability_line("Strength: ", &Ability::Strg, base, effective),
+            self.build_ability_line("Agility: ", &Ability::Agil, base, effective),
+            self.build_ability_line("Defence: ", &Ability::Def, base, effective),
         ];
+
+        if stat_points > 0 {
+            content.push(Spans::from(""));
+            content.push(Spans::from(vec![
+                Span::raw("Stat Points: "),
+                Span::styled(format!("{}", stat_points), Style::default().fg(Color::LightMagenta)),
+            ]));
+            content.push(Spans::from(vec![
+                Span::styled("[1]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Strg "),
+                Span::styled("[2]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Agil "),
+                Span::styled("[3]", Style::default().fg(Color::Yellow)),
+                Span::raw(" Def"),
+            ]));
+        }
         
         Paragraph::new(content)
         .wrap(Wrap { trim: true })
@@ -55,6 +97,21 @@ impl CharacterView {
         .block(self.build_block())
     }
 
+    fn build_ability_line(&self, label: &'static str, ability: &Ability, base: &Character, effective: &Character) -> Spans {
+        let base_value = base.get_ability(ability);
+        let value = effective.get_ability(ability);
+
+        let mut line = vec![
+            Span::raw(label),
+            Span::styled(format!("{}", value), Style::default().fg(Color::Green)),
+        ];
+        if value != base_value {
+            line.push(Span::styled(format!(" ({})", base_value), Style::default().fg(Color::DarkGray)));
+        }
+
+        Spans::from(line)
+    }
+
     fn build_skill_section(&self, skill_list: &[Rc<Skill>]) -> Paragraph {
 
         let mut skills = Vec::new();
@@ -82,7 +139,19 @@ impl CharacterView {
 
 
     pub fn handle_input(
-        &self, _key_code: KeyCode, _game_handler: &mut GameHandler) -> Result<bool, Error> {
+        &self, key_code: KeyCode, game_handler: &mut GameHandler) -> Result<bool, Error> {
+        let ability = match key_code {
+            KeyCode::Char('1') => Ability::Strg,
+            KeyCode::Char('2') => Ability::Agil,
+            KeyCode::Char('3') => Ability::Def,
+            _ => return Ok(true),
+        };
+
+        let has_points = game_handler.state().character.stat_points > 0;
+        if has_points {
+            game_handler.spend_stat_point(ability)?;
+        }
+
         Ok(true)
     }
 }
\ No newline at end of file
@@ -0,0 +1,60 @@
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::{Style, Color};
+use tui::widgets::{Block, Borders, BorderType, Gauge, Widget};
+
+/// Shows how many level points are collected until the final room is entered.
+#[derive(Debug, Clone)]
+pub struct LevelProgress {
+    points: u16,
+    required: u16,
+}
+
+impl LevelProgress {
+    pub fn new(points: u16, required: u16) -> LevelProgress {
+        LevelProgress { points, required }
+    }
+
+    fn get_ratio(&self) -> f64 {
+        if self.required == 0 {
+            return 1.0;
+        }
+        (f64::from(self.points) / f64::from(self.required)).min(1.0)
+    }
+}
+
+impl Widget for LevelProgress {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let gauge = Gauge::default()
+            .block(Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::White))
+                .title("Level Progress")
+                .border_type(BorderType::Rounded))
+            .gauge_style(Style::default().fg(Color::Cyan).bg(Color::Black))
+            .label(format!("{}/{}", self.points, self.required))
+            .ratio(self.get_ratio());
+
+        gauge.render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ratio() {
+        assert_eq!(LevelProgress::new(25, 100).get_ratio(), 0.25);
+    }
+
+    #[test]
+    fn test_ratio_exceeded() {
+        assert_eq!(LevelProgress::new(120, 100).get_ratio(), 1.0);
+    }
+
+    #[test]
+    fn test_ratio_without_required_points() {
+        assert_eq!(LevelProgress::new(0, 0).get_ratio(), 1.0);
+    }
+}
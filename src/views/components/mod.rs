@@ -1,3 +1,5 @@
 mod message_block;
+mod level_progress;
 
-pub use message_block::*;
\ No newline at end of file
+pub use message_block::*;
+pub use level_progress::*;
@@ -3,8 +3,8 @@ use tui::{
     buffer::Buffer,
     layout::Rect,
     layout::Alignment,
-    widgets::{ Widget, Paragraph, Block, Borders, BorderType },
-    style::{ Color, Style }
+    widgets::{ Widget, Paragraph, Block, Borders, BorderType, Wrap },
+    style::{ Color, Style, Modifier }
 };
 
 use tui::text::Spans;
@@ -23,12 +23,13 @@ impl BattleHistory {
 
     fn get_entry(&self, event: &BattleEvent) -> Spans {
         Spans::from(vec![
-            Span::raw(event.title.to_owned())
+            Span::styled(format!("{}: ", event.title), Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(event.effect.to_owned())
         ])
     }
 
     fn get_text_content(&self) -> Vec<Spans> {
-        let content: Vec<Spans> = self.history.events.iter().map(|event| self.get_entry(event)).collect();
+        let content: Vec<Spans> = self.history.events.iter().rev().map(|event| self.get_entry(event)).collect();
         content
     }
 }
@@ -39,6 +40,7 @@ impl Widget for BattleHistory {
 
         let message = Paragraph::new(self.get_text_content())
             .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
             .block(Block::default()
                 .borders(Borders::ALL)
                 .style(Style::default().fg(Color::White))
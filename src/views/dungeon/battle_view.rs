@@ -5,23 +5,23 @@ use tui::{
     style::{Color, Style, Modifier},
     text::{ Text, Span, Spans },
     widgets::{
-        Block, BorderType, Borders, Paragraph, ListItem, List, ListState
+        Block, BorderType, Borders, Paragraph, ListItem, List, ListState, Wrap
     },
 };
 use crossterm::event::KeyCode;
 
-use crate::models::{ BattleEvent, BattleEvents, attack_options::* };
+use crate::models::{ ActorValues, attack_options::* };
+use crate::models::models::ItemType;
+use crate::state::OwnedItem;
 use crate::{ GameHandler, GameState, Error };
 
 use crate::views::dungeon::battle::{ attack_helper::build_damage_text, BattleHistory };
 
-use crate::battle_handler::BattleHandler;
-
 pub struct BattleView {
     menu_state: ListState,
     menu_list: Vec<String>,
-    battle_events: BattleEvents,
-    battle_handler: BattleHandler,
+    /// The last failed action, shown until the next input.
+    message: Option<String>,
 }
 
 impl BattleView {
@@ -32,8 +32,7 @@ impl BattleView {
         BattleView {
             menu_state: state,
             menu_list: vec!["Attack".to_string(), "Items".to_string(), "Other".to_string()],
-            battle_events: BattleEvents::new(),
-            battle_handler: BattleHandler::new(),
+            message: None,
         }
     }
     pub fn render(&self, frame: &mut Frame<impl Backend>, rect: Rect, game_state: &GameState) -> Result<(), Error> {
@@ -45,7 +44,8 @@ impl BattleView {
             .split(rect);
 
         let top_chunks = Layout::default().direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(20), Constraint::Percentage(80)].as_ref()).split(main_chunks[0]);
+            .constraints([Constraint::Percentage(20), Constraint::Percentage(60), Constraint::Percentage(20)].as_ref())
+            .split(main_chunks[0]);
 
         let menu_chunks = Layout::default()
             .direction(Direction::Horizontal)
@@ -54,14 +54,27 @@ impl BattleView {
             )
             .split(main_chunks[1]);
 
-        let history = BattleHistory::new(self.battle_events.clone());
+        let history = BattleHistory::new(game_state.battle_events.clone());
         frame.render_widget(history, top_chunks[0]);
         frame.render_widget(self.render_image(), top_chunks[1]);
+        frame.render_widget(self.build_combatants(&game_state.battle_actors), top_chunks[2]);
         
         let mut menu_state = self.menu_state.clone();
         frame.render_stateful_widget(self.build_battle_menu(), menu_chunks[0], &mut menu_state);
 
-        self.render_action_part(frame, menu_chunks[1], game_state)?;
+        let action_rect = match &self.message {
+            Some(message) => {
+                let action_chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
+                    .split(menu_chunks[1]);
+                let message = Paragraph::new(Span::styled(message.to_owned(), Style::default().fg(Color::Yellow)));
+                frame.render_widget(message, action_chunks[1]);
+                action_chunks[0]
+            }
+            None => menu_chunks[1],
+        };
+        self.render_action_part(frame, action_rect, game_state)?;
         
         Ok(())
     }
@@ -71,7 +84,9 @@ impl BattleView {
             Some(0) => {
                 self.render_attack_menu(frame, rect, game_state)?;
             }
-            Some(1) => {}
+            Some(1) => {
+                frame.render_widget(self.build_item_menu(&game_state.owned_items), rect);
+            }
             Some(3) => {}
             Some(_) | None => {}
         }
@@ -79,6 +94,43 @@ impl BattleView {
         Ok(())
     }
 
+    /// The indices of the consumables in `owned_items`, the first nine can be used with the number keys.
+    fn find_consumables(owned_items: &[OwnedItem]) -> Vec<usize> {
+        owned_items.iter()
+            .enumerate()
+            .filter(|(_, owned)| owned.item.item_type == ItemType::Consumable)
+            .map(|(index, _)| index)
+            .take(9)
+            .collect()
+    }
+
+    fn build_item_menu(&self, owned_items: &[OwnedItem]) -> Paragraph {
+        let consumables = BattleView::find_consumables(owned_items);
+        let content: Vec<Spans> = if consumables.is_empty() {
+            vec![Spans::from("No consumables left.")]
+        } else {
+            consumables.iter().enumerate().map(|(key, index)| {
+                let owned = &owned_items[*index];
+                let effects: Vec<String> = owned.item.effects.iter().map(|effect| effect.to_string()).collect();
+                Spans::from(vec![
+                    Span::styled(format!("[{}]", key + 1), Style::default().fg(Color::Yellow)),
+                    Span::raw(" "),
+                    Span::styled(owned.item.name.to_owned(), Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(format!(" x{}  ", owned.quantity)),
+                    Span::styled(effects.join(", "), Style::default().fg(Color::LightBlue)),
+                ])
+            }).collect()
+        };
+
+        Paragraph::new(content)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .style(Style::default().fg(Color::White))
+                    .border_type(BorderType::Rounded),
+            )
+    }
+
     fn render_attack_menu(&self, frame: &mut Frame<impl Backend>, rect: Rect, game_state: &GameState) -> Result<(), Error> {
         let action_rows = Layout::default()
                 .direction(Direction::Vertical)
@@ -136,6 +188,41 @@ impl BattleView {
             )
     }
 
+    fn build_combatants(&self, actors: &[ActorValues]) -> Paragraph {
+        let content: Vec<Spans> = actors.iter().flat_map(|actor| {
+            let style = match (actor.is_player, actor.hp) {
+                (_, 0) => Style::default().fg(Color::DarkGray),
+                (true, _) => Style::default().fg(Color::LightGreen),
+                (false, _) => Style::default().fg(Color::LightRed),
+            };
+            let mut lines = vec![Spans::from(vec![
+                Span::styled(actor.name.to_owned(), style),
+                Span::raw(format!(" {}/{}", actor.hp, actor.hp_max)),
+            ])];
+
+            if !actor.status_effects.is_empty() && actor.hp > 0 {
+                let mut statuses = vec![Span::raw("  ")];
+                for status in &actor.status_effects {
+                    let color = if status.kind.is_negative() { Color::LightMagenta } else { Color::LightCyan };
+                    statuses.push(Span::styled(format!("{} {}", status.kind, status.turns), Style::default().fg(color)));
+                    statuses.push(Span::raw(" "));
+                }
+                lines.push(Spans::from(statuses));
+            }
+            lines
+        }).collect();
+
+        Paragraph::new(content)
+            .wrap(Wrap { trim: false })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .style(Style::default().fg(Color::White))
+                    .title("Combatants")
+                    .border_type(BorderType::Rounded),
+            )
+    }
+
     fn build_battle_menu(&self) -> List {
         let items: Vec<ListItem> = self.menu_list.iter().map(|text| ListItem::new(text.to_owned())).collect();
 
@@ -160,7 +247,7 @@ impl BattleView {
                 Paragraph::new("N/A")
             }
             AttackOption::Attack(description) => {
-                let title = vec![
+                let mut title = vec![
                     Spans::from(
                         vec![
                             Span::styled(format!("[{}]", key), Style::default().fg(Color::Yellow)),
@@ -177,6 +264,18 @@ impl BattleView {
                             Style::default().fg(Color::LightRed)),
                     ])
                 ];
+                if !description.special_effect.is_empty() {
+                    title.push(Spans::from(vec![
+                        Span::raw("    "),
+                        Span::styled(description.special_effect.to_owned(), Style::default().fg(Color::LightMagenta)),
+                    ]));
+                }
+                for status in &description.status_effects {
+                    title.push(Spans::from(vec![
+                        Span::raw("    "),
+                        Span::styled(status.to_string(), Style::default().fg(Color::LightMagenta)),
+                    ]));
+                }
                 Paragraph::new(title)
             }
         };
@@ -189,6 +288,8 @@ impl BattleView {
     }
 
     pub fn handle_input(&mut self, key_code: KeyCode, game_handler: &mut GameHandler) -> Result<bool, Error> {
+        self.message = None;
+
         match key_code {
             KeyCode::Char('w') | KeyCode::Up => {
                 self.menu_up();
@@ -197,13 +298,14 @@ impl BattleView {
                 self.menu_down();
             }
             _ => {
-                match self.menu_state.selected() {
-                    Some(0) => {
-                        self.handle_attack_input(key_code, game_handler);
-                    }
-                    Some(1) => {}
-                    Some(3) => {}
-                    Some(_) | None => {}
+                let result = match self.menu_state.selected() {
+                    Some(0) => self.handle_attack_input(key_code, game_handler),
+                    Some(1) => self.handle_item_input(key_code, game_handler),
+                    Some(_) | None => Ok(()),
+                };
+                // A rejected action keeps the battle running.
+                if let Err(error) = result {
+                    self.message = Some(error.to_string());
                 }
             }
         }
@@ -211,22 +313,42 @@ impl BattleView {
         Ok(true)
     }
 
-    fn handle_attack_input(&mut self, key_code: KeyCode, _game_handler: &mut GameHandler) {
+    fn handle_attack_input(&mut self, key_code: KeyCode, game_handler: &mut GameHandler) -> Result<(), Error> {
         match key_code {
             KeyCode::Char('1') => {
-                self.battle_events.events.push(BattleEvent::default())
+                game_handler.execute_battle_action(0)?;
             }
             KeyCode::Char('2') => {
-
+                game_handler.execute_battle_action(1)?;
             }
             KeyCode::Char('3') => {
-
+                game_handler.execute_battle_action(2)?;
             }
             KeyCode::Char('4') => {
-
+                game_handler.execute_battle_action(3)?;
             }
             _ => {}
         }
+
+        Ok(())
+    }
+
+    fn handle_item_input(&mut self, key_code: KeyCode, game_handler: &mut GameHandler) -> Result<(), Error> {
+        let key = match key_code {
+            KeyCode::Char(c) => c.to_digit(10),
+            _ => None,
+        };
+
+        if let Some(key) = key.filter(|key| *key > 0) {
+            let consumables = BattleView::find_consumables(&game_handler.state().owned_items);
+            if let Some(index) = consumables.get(key as usize - 1) {
+                if game_handler.can_use_item(*index) {
+                    game_handler.use_item(*index)?;
+                }
+            }
+        }
+
+        Ok(())
     }
 
     fn menu_down(&mut self) {
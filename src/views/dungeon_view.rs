@@ -1,7 +1,11 @@
-use crate::models::models::{ Reward, RewardType, Choice, ItemType, BadResult, BadResultType };
+use std::rc::Rc;
+use crate::models::models::{ Ability, Character, CharacterClass, Reward, RewardType, Choice, ItemType, BadResult, BadResultType, CheckResult };
+use crate::random::CheckOutcome;
 use crate::GameHandler;
 use crate::Error;
 use crate::views::dungeon::BattleView;
+use crate::views::CharacterCreationView;
+use crate::views::components::LevelProgress;
 use tui::text::Spans;
 use tui::text::Span;
 use tui::widgets::Wrap;
@@ -21,15 +25,32 @@ use ::tui::backend::Backend;
 
 pub struct DungeonView {
     battle_view: BattleView,
+    creation: Option<CharacterCreationView>,
 }
 
 impl DungeonView {
     pub fn new() -> DungeonView {
         DungeonView {
             battle_view: BattleView::new(),
+            creation: None,
         }
     }
+
+    /// Shows the new game screen prefilled with the given character.
+    pub fn start_character_creation(&mut self, character: &Character, classes: &[Rc<CharacterClass>], can_cancel: bool) {
+        self.creation = Some(CharacterCreationView::new(character, classes, can_cancel));
+    }
+
+    pub fn is_creating_character(&self) -> bool {
+        self.creation.is_some()
+    }
+
     pub fn render(&self, frame: &mut Frame<impl Backend>, rect: Rect, game_state: &GameState) -> Result<(), Error> {
+        if let Some(creation) = &self.creation {
+            creation.render(frame, rect);
+            return Ok(());
+        }
+
         match game_state.dungeon_state {
             DungeonState::Room => {
                 self.render_room(frame, rect, game_state)?;
@@ -43,6 +64,12 @@ impl DungeonView {
             DungeonState::Encounter => {
                 self.battle_view.render(frame, rect, game_state)?;
             }
+            DungeonState::CampaignComplete => {
+                self.render_campaign_complete_screen(frame, rect, game_state);
+            }
+            DungeonState::GameOver => {
+                self.render_game_over_screen(frame, rect, game_state);
+            }
         }
         Ok(())
     }
@@ -51,7 +78,7 @@ impl DungeonView {
         let dungeon_chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints(
-                [Constraint::Length(3), Constraint::Min(2), Constraint::Length(5)].as_ref(),
+                [Constraint::Length(3), Constraint::Min(2), Constraint::Length(5), Constraint::Length(3)].as_ref(),
             )
             .split(rect);
 
@@ -59,10 +86,12 @@ impl DungeonView {
             Some(room) => room,
             None => return Err(Error::GameDataError("No Room found".to_string())),
         };
+        let level = game_state.get_current_level()?;
 
-        frame.render_widget(self.build_title(&room.title), dungeon_chunks[0]);
+        frame.render_widget(self.build_room_header(&level.name, &room.title), dungeon_chunks[0]);
         frame.render_widget(self.build_body(&room.text), dungeon_chunks[1]);
         frame.render_widget(self.build_choice_widget(&room.choices), dungeon_chunks[2]);
+        frame.render_widget(LevelProgress::new(game_state.level_points, level.level_points), dungeon_chunks[3]);
 
         Ok(())
     }
@@ -75,7 +104,7 @@ impl DungeonView {
         )
         .split(rect);
 
-        frame.render_widget(self.build_result_widget(&game_state.last_rewards), dungeon_chunks[0]);
+        frame.render_widget(self.build_result_widget(game_state), dungeon_chunks[0]);
         frame.render_widget(self.build_confirm_widget(), dungeon_chunks[1]);
     }
 
@@ -97,47 +126,124 @@ impl DungeonView {
             );
 
         frame.render_widget(title, dungeon_chunks[0]);
-        frame.render_widget(self.build_failure_widget(&game_state.last_bad_results), dungeon_chunks[1]);
+        frame.render_widget(self.build_failure_widget(game_state), dungeon_chunks[1]);
         frame.render_widget(self.build_confirm_widget(), dungeon_chunks[2]);
     }
 
-    fn build_result_widget(&self, last_rewards: &Vec<Reward>) -> Paragraph {
-        let mut content: Vec<Spans> = Vec::new();
+    fn render_campaign_complete_screen(&self, frame: &mut Frame<impl Backend>, rect: Rect, game_state: &GameState) {
+        let dungeon_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [Constraint::Length(3), Constraint::Min(2), Constraint::Length(5)].as_ref(),
+        )
+        .split(rect);
+
+        let text = format!(
+            "You cleared every level of the dungeon and gained {} experience points on the way.",
+            game_state.character.xp);
+
+        frame.render_widget(self.build_title("Campaign Complete"), dungeon_chunks[0]);
+        frame.render_widget(self.build_body(&text), dungeon_chunks[1]);
+        frame.render_widget(self.build_action_widget(&["New Game"]), dungeon_chunks[2]);
+    }
+
+    fn render_game_over_screen(&self, frame: &mut Frame<impl Backend>, rect: Rect, game_state: &GameState) {
+        let dungeon_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [Constraint::Length(3), Constraint::Min(2), Constraint::Length(5)].as_ref(),
+        )
+        .split(rect);
+
+        let items: Vec<String> = game_state.owned_items.iter().map(|owned| match owned.quantity {
+            1 => owned.item.name.to_owned(),
+            quantity => format!("{} x{}", owned.item.name, quantity),
+        }).collect();
+        let items = if items.is_empty() { "none".to_string() } else { items.join(", ") };
+
+        let content = vec![
+            Spans::from(vec![
+                Span::raw("Rooms visited: "),
+                Span::styled(format!("{}", game_state.rooms_visited), Style::default().fg(Color::LightCyan)),
+            ]),
+            Spans::from(vec![
+                Span::raw("Experience Points: "),
+                Span::styled(format!("{}", game_state.character.xp), Style::default().fg(Color::LightGreen)),
+            ]),
+            Spans::from(vec![
+                Span::raw("Items: "),
+                Span::styled(items, Style::default().fg(Color::LightYellow)),
+            ]),
+        ];
+
+        let summary = Paragraph::new(content)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .block(self.build_block());
+
+        frame.render_widget(self.build_title("Game Over"), dungeon_chunks[0]);
+        frame.render_widget(summary, dungeon_chunks[1]);
+        frame.render_widget(self.build_action_widget(&["Restart", "Quit"]), dungeon_chunks[2]);
+    }
+
+    fn build_result_widget(&self, game_state: &GameState) -> Paragraph {
+        let mut content: Vec<Spans> = self.build_check_lines(&game_state.last_checks);
+        content.append(&mut self.build_bad_result_lines(&game_state.last_bad_results));
+        content.append(&mut self.build_reward_lines(&game_state.last_rewards));
+
+        Paragraph::new(content)
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .style(Style::default().fg(Color::White))
+                    .border_type(BorderType::Rounded),
+            )
+    }
+
+    fn build_failure_widget(&self, game_state: &GameState) -> Paragraph {
+        let mut content: Vec<Spans> = self.build_check_lines(&game_state.last_checks);
+        content.append(&mut self.build_bad_result_lines(&game_state.last_bad_results));
+        content.append(&mut self.build_reward_lines(&game_state.last_rewards));
+
+        Paragraph::new(content)
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .style(Style::default().fg(Color::White))
+                    .border_type(BorderType::Rounded),
+            )
+
+    }
 
-        for reward in last_rewards {
+    fn build_reward_lines(&self, rewards: &[Reward]) -> Vec<Spans<'static>> {
+        rewards.iter().map(|reward| {
             let mut reward_text: Vec<Span> = Vec::new();
             if reward.amount > 1 {
                 reward_text.push(Span::raw(format!("{} ", reward.amount)))
             }
             let color = match &reward.reward_type {
                 RewardType::Xp => Color::LightGreen,
+                RewardType::LevelPoints => Color::LightCyan,
+                RewardType::LevelUp => Color::LightMagenta,
                 RewardType::Item(it) => {
                     match it {
                         ItemType::Armor => Color::LightYellow,
                         ItemType::Weapon => Color::LightRed,
+                        ItemType::Consumable => Color::LightBlue,
                     }
                 }
                 _ => Color::White,
             };
-            reward_text.push(Span::styled(format!("{}", reward.name), Style::default().fg(color)));
+            reward_text.push(Span::styled(reward.name.to_owned(), Style::default().fg(color)));
 
-            content.push(Spans::from(reward_text));
-        }
-        
-        Paragraph::new(content)
-            .alignment(Alignment::Center)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .style(Style::default().fg(Color::White))
-                    .border_type(BorderType::Rounded),
-            )
+            Spans::from(reward_text)
+        }).collect()
     }
 
-    fn build_failure_widget(&self, last_bad_results: &Vec<BadResult>) -> Paragraph {
-        let mut content: Vec<Spans> = Vec::new();
-
-        for result in last_bad_results {
+    fn build_bad_result_lines(&self, bad_results: &[BadResult]) -> Vec<Spans<'static>> {
+        bad_results.iter().map(|result| {
             let mut text: Vec<Span> = Vec::new();
             if result.amount > 1 {
                 text.push(Span::raw(format!("{} ", result.amount)))
@@ -148,27 +254,48 @@ impl DungeonView {
             };
             text.push(Span::styled(format!("{}", result.name), Style::default().fg(color)));
 
-            content.push(Spans::from(text));
-        }
+            Spans::from(text)
+        }).collect()
+    }
 
-        Paragraph::new(content)
-            .alignment(Alignment::Center)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .style(Style::default().fg(Color::White))
-                    .border_type(BorderType::Rounded),
-            )
+    fn build_check_lines(&self, checks: &[CheckResult]) -> Vec<Spans<'static>> {
+        checks.iter().map(|check| {
+            let ability = match check.ability {
+                Ability::Strg => "Strength",
+                Ability::Agil => "Agility",
+                Ability::Def => "Defence",
+            };
+            let (outcome, color) = match check.roll.outcome {
+                CheckOutcome::CriticalSuccess => ("Critical Success", Color::LightGreen),
+                CheckOutcome::Success => ("Success", Color::Green),
+                CheckOutcome::PartialSuccess => ("Partial Success", Color::Yellow),
+                CheckOutcome::Failure => ("Failure", Color::Red),
+                CheckOutcome::Fumble => ("Fumble", Color::LightRed),
+            };
 
+            Spans::from(vec![
+                Span::raw(format!("{} check ({} of {} successes): ", ability, check.roll.successes, check.dificulty)),
+                Span::styled(outcome, Style::default().fg(color).add_modifier(Modifier::BOLD)),
+            ])
+        }).collect()
     }
 
     fn build_confirm_widget(&self) -> Paragraph {
-        let content: Vec<Span> = vec![
-            Span::styled("[", Style::default().fg(Color::Yellow)),
-            Span::styled(format!("1"), Style::default().add_modifier(Modifier::BOLD)),
-            Span::styled("]", Style::default().fg(Color::Yellow)),
-            Span::raw(" OK")
-        ];
+        self.build_action_widget(&["OK"])
+    }
+
+    fn build_action_widget(&self, labels: &[&str]) -> Paragraph {
+        let mut content: Vec<Span> = Vec::new();
+
+        labels.iter().enumerate().for_each(|(idx, label)| {
+            if idx > 0 {
+                content.push(Span::raw("  |  "));
+            }
+            content.push(Span::styled("[", Style::default().fg(Color::Yellow)));
+            content.push(Span::styled(format!("{}", idx + 1), Style::default().add_modifier(Modifier::BOLD)));
+            content.push(Span::styled("]", Style::default().fg(Color::Yellow)));
+            content.push(Span::raw(format!(" {}", label)));
+        });
 
         Paragraph::new(Spans::from(content))
             .alignment(Alignment::Center)
@@ -188,6 +315,14 @@ impl DungeonView {
             .block(self.build_block())
     }
 
+    fn build_room_header(&self, level_name: &str, title: &str) -> Paragraph {
+        Paragraph::new(title.to_owned())
+            .style(Style::default().add_modifier(Modifier::BOLD))
+            .wrap(Wrap { trim: true })
+            .alignment(Alignment::Center)
+            .block(self.build_block().title(level_name.to_owned()))
+    }
+
     fn build_body(&self, text: &str) -> Paragraph {
         Paragraph::new(text.to_owned())
             .alignment(Alignment::Center)
@@ -228,7 +363,20 @@ impl DungeonView {
             .border_type(BorderType::Double)
     }
 
+    /// Opens the new game screen with the character of the finished run.
+    fn start_new_game(&mut self, game_handler: &GameHandler) {
+        let character = game_handler.state().character.clone();
+        self.start_character_creation(&character, game_handler.get_classes(), true);
+    }
+
     pub fn handle_input(&mut self, key_code: KeyCode, game_handler: &mut GameHandler) -> Result<bool, Error> {
+        if let Some(creation) = &mut self.creation {
+            if !creation.handle_input(key_code, game_handler)? {
+                self.creation = None;
+            }
+            return Ok(true);
+        }
+
         let state = game_handler.get_dungeon_state();
         match state {
             DungeonState::Room => {
@@ -254,7 +402,23 @@ impl DungeonView {
             DungeonState::Result | DungeonState::Failure => {
                 match key_code {
                     KeyCode::Char('1') => {
-                        game_handler.set_dungeon_state(DungeonState::Room);
+                        game_handler.confirm_result();
+                    }
+                    _ => {}
+                }
+            }
+            DungeonState::CampaignComplete => {
+                if let KeyCode::Char('1') = key_code {
+                    self.start_new_game(game_handler);
+                }
+            }
+            DungeonState::GameOver => {
+                match key_code {
+                    KeyCode::Char('1') => {
+                        self.start_new_game(game_handler);
+                    }
+                    KeyCode::Char('2') => {
+                        return Ok(false);
                     }
                     _ => {}
                 }
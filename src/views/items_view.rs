@@ -14,6 +14,8 @@ use crate::Error;
 use crate::GameHandler;
 use crate::GameState;
 use crate::Item;
+use crate::models::models::ItemType;
+use crate::state::OwnedItem;
 
 use crate::views::components::MessageBlock;
 
@@ -69,13 +71,27 @@ impl ItemsView {
         .split(layout_chunks[1]);
 
         self.list_length = game_state.owned_items.len();
+        // The last item of a list can be used up.
+        if let Some(selected) = self.list_state.selected() {
+            if selected >= self.list_length {
+                self.list_state.select(Some(self.list_length.saturating_sub(1)));
+            }
+        }
 
 
         let list = self.build_item_list(&game_state.owned_items);
         frame.render_stateful_widget(list, layout_chunks[0], &mut self.list_state.clone());
 
-        let details = self.build_item_detail(&game_state.owned_items);
-        frame.render_widget(details, right_chunks[0]);
+        let selected = self.list_state.selected().and_then(|index| game_state.owned_items.get(index));
+        match selected {
+            Some(owned) => frame.render_widget(self.build_item_detail(&owned.item), right_chunks[0]),
+            None => {
+                let message_block = MessageBlock::new(
+                    "Detail",
+                    Span::raw("No item selected."));
+                frame.render_widget(message_block, right_chunks[0]);
+            }
+        }
 
 
         if game_state.equipped_items.len() > 0 {
@@ -91,7 +107,7 @@ impl ItemsView {
         Ok(())
     }
 
-    fn build_item_list(&self, item_list: &Vec<Rc<Item>>) -> List {
+    fn build_item_list(&self, item_list: &[OwnedItem]) -> List {
         let pets = Block::default()
             .borders(Borders::ALL)
             .style(Style::default().fg(Color::White))
@@ -100,9 +116,13 @@ impl ItemsView {
 
         let items: Vec<_> = item_list
             .iter()
-            .map(|pet| {
+            .map(|owned| {
+                let name = match owned.quantity {
+                    1 => owned.item.name.clone(),
+                    quantity => format!("{} x{}", owned.item.name, quantity),
+                };
                 ListItem::new(Spans::from(vec![Span::styled(
-                    pet.name.clone(),
+                    name,
                     Style::default(),
                 )]))
             })
@@ -116,20 +136,13 @@ impl ItemsView {
         )
     }
 
-    fn build_item_detail(&self,  item_list: &Vec<Rc<Item>>) -> Table {
-        let list_state = &self.list_state;
-
-        let selected_item = item_list
-        .get(
-            list_state
-                .selected()
-                .expect("there is always a selected pet"),
-        )
-        .expect("exists") // todo .. two methods. render info if there is no item instead of panicking
-        .clone();
-
+    fn build_item_detail(&self, selected_item: &Item) -> Table {
+        let title = match selected_item.item_type {
+            ItemType::Consumable => "Detail - [u] Use",
+            _ => "Detail - [e] Equip",
+        };
     
-        Table::new(vec![self.build_item_row(&selected_item)])
+        Table::new(vec![self.build_item_row(selected_item)])
         .header(Row::new(vec![
             Cell::from(Span::styled(
                 "Name",
@@ -139,17 +152,22 @@ impl ItemsView {
                 "Category",
                 Style::default().add_modifier(Modifier::BOLD),
             )),
+            Cell::from(Span::styled(
+                "Effects",
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
         ]))
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .style(Style::default().fg(Color::White))
-                .title("Detail")
+                .title(title)
                 .border_type(BorderType::Plain),
         )
         .widths(&[
             Constraint::Percentage(20),
-            Constraint::Percentage(80),
+            Constraint::Percentage(20),
+            Constraint::Percentage(60),
         ])
     }
 
@@ -166,6 +184,10 @@ impl ItemsView {
                 "Category",
                 Style::default().add_modifier(Modifier::BOLD),
             )),
+            Cell::from(Span::styled(
+                "Effects",
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
         ]))
         .block(
             Block::default()
@@ -176,14 +198,20 @@ impl ItemsView {
         )
         .widths(&[
             Constraint::Length(20),
+            Constraint::Length(12),
             Constraint::Min(20),
         ])
     }
 
     fn build_item_row(&self, item: &Item) -> Row {
+        let effects: Vec<String> = item.effects.iter().map(|effect| effect.to_string())
+            .chain(item.attacks.iter().map(|attack| attack.title.to_owned()))
+            .collect();
+
         Row::new(vec![
             Cell::from(Span::raw(item.name.to_string())),
             Cell::from(Span::raw(item.item_type.to_string())),
+            Cell::from(Span::raw(effects.join(", "))),
         ])
     }
 
@@ -213,7 +241,19 @@ impl ItemsView {
             }
             KeyCode::Char('e') => {
                 if let Some(selected) = list_state.selected() {
-                    game_handler.equip_item_by_index(selected)?;
+                    let is_equipment = matches!(
+                        game_handler.state().owned_items.get(selected),
+                        Some(owned) if owned.item.item_type != ItemType::Consumable);
+                    if is_equipment {
+                        game_handler.equip_item_by_index(selected)?;
+                    }
+                }
+            }
+            KeyCode::Char('u') => {
+                if let Some(selected) = list_state.selected() {
+                    if game_handler.can_use_item(selected) {
+                        game_handler.use_item(selected)?;
+                    }
                 }
             }
             _ => {}
@@ -11,12 +11,19 @@ use crossterm::event::KeyCode;
 use crate::Error;
 use crate::GlobalHandler;
 use crate::GameState;
+use crate::state::SAVE_PATH;
 
-pub struct MenuView {}
+pub struct MenuView {
+    message: Option<String>,
+}
 
 impl MenuView {
-    pub fn render(&self, frame: &mut Frame<impl Backend>, rect: Rect, _game_state: &GameState) -> Result<(), String> {
-        let content = vec![
+    pub fn new() -> MenuView {
+        MenuView { message: None }
+    }
+
+    pub fn render(&self, frame: &mut Frame<impl Backend>, rect: Rect, game_state: &GameState) -> Result<(), String> {
+        let mut content = vec![
             Spans::from(vec![
                 Span::styled("Menu", Style::default().add_modifier(Modifier::BOLD))
             ]),
@@ -24,11 +31,28 @@ impl MenuView {
                 Span::styled("[r]", Style::default().fg(Color::Cyan)),
                 Span::raw(" Reset Game"),
             ]),
+            Spans::from(vec![
+                Span::styled("[s]", Style::default().fg(Color::Cyan)),
+                Span::raw(" Save Game"),
+            ]),
+            Spans::from(vec![
+                Span::styled("[l]", Style::default().fg(Color::Cyan)),
+                Span::raw(" Load Game"),
+            ]),
             Spans::from(vec![
                 Span::styled("[q]", Style::default().fg(Color::Cyan)),
                 Span::raw(" Quit"),
             ]),
+            Spans::from(vec![]),
+            Spans::from(vec![
+                Span::styled(format!("Seed: {}", game_state.seed), Style::default().fg(Color::DarkGray)),
+            ]),
         ];
+
+        if let Some(message) = &self.message {
+            content.push(Spans::from(vec![]));
+            content.push(Spans::from(vec![Span::styled(message.to_owned(), Style::default().fg(Color::Yellow))]));
+        }
         
         let p = Paragraph::new(content)
         .wrap(Wrap { trim: true })
@@ -49,7 +73,7 @@ impl MenuView {
 
 
     pub fn handle_input(
-        &self, key_code: KeyCode, game_handler: &mut GameHandler, global_handlers: &mut GlobalHandler)
+        &mut self, key_code: KeyCode, game_handler: &mut GameHandler, global_handlers: &mut GlobalHandler)
         -> Result<bool, Error> {
 
         match key_code {
@@ -60,6 +84,19 @@ impl MenuView {
             KeyCode::Char('r') => {
                 // todo needs game_handler
                 game_handler.reset_game()?;
+                self.message = None;
+            }
+            KeyCode::Char('s') => {
+                self.message = match game_handler.save_game(SAVE_PATH) {
+                    Ok(()) => Some("Game saved.".to_string()),
+                    Err(err) => Some(err.to_string()),
+                };
+            }
+            KeyCode::Char('l') => {
+                self.message = match game_handler.load_game(SAVE_PATH) {
+                    Ok(()) => Some("Game loaded.".to_string()),
+                    Err(err) => Some(err.to_string()),
+                };
             }
             _ => {}
         }
@@ -2,6 +2,7 @@ mod dungeon_view;
 mod menu_view;
 mod character_view;
 mod items_view;
+mod character_creation_view;
 
 mod components;
 mod dungeon;
@@ -9,4 +10,5 @@ mod dungeon;
 pub use dungeon_view::*;
 pub use menu_view::*;
 pub use character_view::*;
-pub use items_view::*;
\ No newline at end of file
+pub use items_view::*;
+pub use character_creation_view::*;
\ No newline at end of file